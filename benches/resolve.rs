@@ -0,0 +1,36 @@
+//! Compares resolving an id with a fresh volume scan against a reused [`VolumeResolver`].
+#[cfg(target_family = "windows")]
+mod bench {
+    use criterion::{criterion_group, Criterion};
+    use file_path_from_id::windows::{path_from_id, VolumeResolver};
+    use std::{env, fs};
+
+    fn resolve(c: &mut Criterion) {
+        let path = env::temp_dir().join("file_path_from_id_bench.txt");
+        fs::write(&path, "").unwrap();
+        let id = file_id::get_file_id(&path).unwrap();
+
+        let mut group = c.benchmark_group("path_from_id");
+        group.bench_function("volume scan", |b| {
+            b.iter(|| VolumeResolver::new().unwrap().path_from_id(&id).unwrap())
+        });
+
+        group.bench_function("global cache", |b| b.iter(|| path_from_id(&id).unwrap()));
+
+        let resolver = VolumeResolver::new().unwrap();
+        group.bench_function("volume resolver", |b| {
+            b.iter(|| resolver.path_from_id(&id).unwrap())
+        });
+
+        group.finish();
+        fs::remove_file(&path).unwrap();
+    }
+
+    criterion_group!(benches, resolve);
+}
+
+#[cfg(target_family = "windows")]
+criterion::criterion_main!(bench::benches);
+
+#[cfg(not(target_family = "windows"))]
+fn main() {}
@@ -5,7 +5,7 @@ use std::fs;
 use std::sync::mpsc;
 use std::time::Duration;
 
-const WATCH_PATH: &'static str = "watched";
+const WATCH_PATH: &str = "watched";
 
 /// # Notes
 /// + Must be run from the `examples/basic` folder due to relative paths.
@@ -40,7 +40,7 @@ fn main() {
                     println!("{event:?}\n");
                     match &event.paths[..] {
                         [path] => {
-                            let Some(info) = cache.cached_file_id(&path) else {
+                            let Some(info) = cache.cached_file_id(path) else {
                                 println!("path not cached\n");
                                 continue;
                             };
@@ -52,7 +52,7 @@ fn main() {
                         }
 
                         [from, to] => {
-                            match cache.cached_file_id(&from) {
+                            match cache.cached_file_id(from) {
                                 None => {
                                     println!("from path not cached\n");
                                 }
@@ -63,7 +63,7 @@ fn main() {
                                 },
                             }
 
-                            match cache.cached_file_id(&to) {
+                            match cache.cached_file_id(to) {
                                 None => {
                                     println!("to path not cached\n");
                                 }
@@ -0,0 +1,216 @@
+use crate::{Error, PlatformResolver, Resolver};
+use file_id::FileId;
+use std::{
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+};
+
+/// Cache of resolved paths, evicting the least recently used id once full.
+///
+/// Paths are not checked when returned from the cache,
+/// so ids should be invalidated when their file is moved or deleted.
+#[derive(Debug)]
+pub struct PathCache<R = PlatformResolver> {
+    resolver: R,
+    capacity: usize,
+
+    /// Paths with the generation they were last used in.
+    paths: HashMap<FileId, (PathBuf, u64)>,
+
+    /// Uses from least to most recent.
+    /// A use is stale if its id has been used again since, or was removed.
+    uses: VecDeque<(u64, FileId)>,
+    generation: u64,
+}
+
+impl PathCache {
+    /// Creates a cache holding at most `capacity` paths.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_resolver(capacity, PlatformResolver)
+    }
+}
+
+impl<R: Resolver> PathCache<R> {
+    /// Creates a cache holding at most `capacity` paths, resolving ids with `resolver`.
+    pub fn with_resolver(capacity: usize, resolver: R) -> Self {
+        Self {
+            resolver,
+            capacity,
+            paths: HashMap::with_capacity(capacity),
+            uses: VecDeque::with_capacity(capacity),
+            generation: 0,
+        }
+    }
+
+    /// Gets the cached path of the id, resolving and caching it if it is not cached.
+    /// Errors are not cached.
+    pub fn get_or_resolve(&mut self, id: &FileId) -> Result<PathBuf, Error> {
+        if self.paths.contains_key(id) {
+            let generation = self.next_generation(id);
+            let (path, used) = self.paths.get_mut(id).unwrap();
+            *used = generation;
+            return Ok(path.clone());
+        }
+
+        let path = self.resolver.path_from_id(id)?;
+        if self.capacity == 0 {
+            return Ok(path);
+        }
+
+        if self.paths.len() >= self.capacity {
+            self.evict();
+        }
+
+        let generation = self.next_generation(id);
+        self.paths.insert(*id, (path.clone(), generation));
+        Ok(path)
+    }
+
+    /// Removes the id from the cache.
+    pub fn invalidate(&mut self, id: &FileId) {
+        self.paths.remove(id);
+    }
+
+    /// Removes every id from the cache.
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.uses.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Records a use of the id, returning its generation.
+    ///
+    /// Stale uses are dropped once they outnumber the cached ids,
+    /// so recording a use is amortized O(1).
+    fn next_generation(&mut self, id: &FileId) -> u64 {
+        if self.uses.len() >= self.capacity.saturating_mul(2).max(1) {
+            let paths = &self.paths;
+            self.uses
+                .retain(|(generation, id)| is_current(paths, id, *generation));
+        }
+
+        self.generation += 1;
+        self.uses.push_back((self.generation, *id));
+        self.generation
+    }
+
+    /// Removes the least recently used id.
+    fn evict(&mut self) {
+        while let Some((generation, id)) = self.uses.pop_front() {
+            if is_current(&self.paths, &id, generation) {
+                self.paths.remove(&id);
+                return;
+            }
+        }
+    }
+}
+
+/// Whether the use is the most recent of a cached id.
+fn is_current(paths: &HashMap<FileId, (PathBuf, u64)>, id: &FileId, generation: u64) -> bool {
+    paths.get(id).is_some_and(|(_, used)| *used == generation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Resolves inode ids to `/<inode number>`, counting resolutions.
+    #[derive(Default)]
+    struct CountingResolver {
+        resolutions: Cell<usize>,
+    }
+
+    impl Resolver for CountingResolver {
+        fn path_from_id(&self, id: &FileId) -> Result<PathBuf, Error> {
+            self.resolutions.set(self.resolutions.get() + 1);
+            match id {
+                FileId::Inode { inode_number, .. } => Ok(PathBuf::from(format!("/{inode_number}"))),
+                _ => Err(Error::NotFound),
+            }
+        }
+    }
+
+    fn id(inode_number: u64) -> FileId {
+        FileId::new_inode(1, inode_number)
+    }
+
+    #[test]
+    fn cached_paths_are_not_resolved_again() {
+        let mut cache = PathCache::with_resolver(2, CountingResolver::default());
+
+        assert_eq!(cache.get_or_resolve(&id(1)).unwrap(), PathBuf::from("/1"));
+        assert_eq!(cache.get_or_resolve(&id(1)).unwrap(), PathBuf::from("/1"));
+        assert_eq!(cache.resolver.resolutions.get(), 1);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = PathCache::with_resolver(2, CountingResolver::default());
+        cache.get_or_resolve(&id(1)).unwrap();
+        cache.get_or_resolve(&id(2)).unwrap();
+        cache.get_or_resolve(&id(1)).unwrap();
+        cache.get_or_resolve(&id(3)).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        cache.get_or_resolve(&id(1)).unwrap();
+        assert_eq!(cache.resolver.resolutions.get(), 3);
+        cache.get_or_resolve(&id(2)).unwrap();
+        assert_eq!(cache.resolver.resolutions.get(), 4);
+    }
+
+    #[test]
+    fn repeated_uses_stay_bounded() {
+        let mut cache = PathCache::with_resolver(2, CountingResolver::default());
+        for _ in 0..100 {
+            cache.get_or_resolve(&id(1)).unwrap();
+            cache.get_or_resolve(&id(2)).unwrap();
+        }
+
+        assert!(cache.uses.len() <= 4);
+        assert_eq!(cache.resolver.resolutions.get(), 2);
+    }
+
+    #[test]
+    fn invalidated_ids_are_resolved_again() {
+        let mut cache = PathCache::with_resolver(2, CountingResolver::default());
+        cache.get_or_resolve(&id(1)).unwrap();
+        cache.invalidate(&id(1));
+        cache.get_or_resolve(&id(2)).unwrap();
+        cache.get_or_resolve(&id(3)).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        cache.get_or_resolve(&id(1)).unwrap();
+        assert_eq!(cache.resolver.resolutions.get(), 4);
+    }
+
+    #[test]
+    fn errors_are_not_cached() {
+        let mut cache = PathCache::with_resolver(2, CountingResolver::default());
+        let id = FileId::new_low_res(1, 2);
+
+        assert_eq!(cache.get_or_resolve(&id), Err(Error::NotFound));
+        assert_eq!(cache.get_or_resolve(&id), Err(Error::NotFound));
+        assert!(cache.is_empty());
+        assert_eq!(cache.resolver.resolutions.get(), 2);
+    }
+
+    #[test]
+    fn zero_capacity_caches_nothing() {
+        let mut cache = PathCache::with_resolver(0, CountingResolver::default());
+        cache.get_or_resolve(&id(1)).unwrap();
+
+        assert!(cache.is_empty());
+    }
+}
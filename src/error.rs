@@ -0,0 +1,188 @@
+use std::{error, fmt, io, path::PathBuf};
+
+#[cfg(target_family = "unix")]
+use crate::unix;
+
+#[cfg(target_family = "windows")]
+use crate::windows;
+
+/// Platform independent error.
+///
+/// Converted from the platform specific errors so
+/// they can be handled the same on every platform.
+#[derive(Debug)]
+pub enum Error {
+    /// The file id variant can not be resolved on this platform.
+    InvalidFileId,
+
+    /// No file matches the id.
+    NotFound,
+
+    /// The resolved path belongs to a different file than the id.
+    IdReused {
+        path: PathBuf,
+    },
+
+    /// The id was not resolved before the deadline.
+    Timeout,
+
+    Io(io::Error),
+}
+
+impl Error {
+    /// Captures the error as an owned value that can be cloned.
+    pub fn snapshot(&self) -> crate::ErrorSnapshot {
+        let (variant, io_error) = match self {
+            Self::InvalidFileId => ("InvalidFileId", None),
+            Self::NotFound => ("NotFound", None),
+            Self::IdReused { .. } => ("IdReused", None),
+            Self::Timeout => ("Timeout", None),
+            Self::Io(err) => ("Io", Some(err)),
+        };
+
+        crate::ErrorSnapshot::new(variant, self, io_error)
+    }
+
+    /// Copies the error for another result.
+    /// IO errors keep their OS error code, or their kind and message.
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            Self::InvalidFileId => Self::InvalidFileId,
+            Self::NotFound => Self::NotFound,
+            Self::IdReused { path } => Self::IdReused { path: path.clone() },
+            Self::Timeout => Self::Timeout,
+            Self::Io(err) => Self::Io(match err.raw_os_error() {
+                Some(code) => io::Error::from_raw_os_error(code),
+                None => io::Error::new(err.kind(), err.to_string()),
+            }),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::NotFound {
+            Self::NotFound
+        } else {
+            Self::Io(err)
+        }
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl From<unix::Error> for Error {
+    fn from(err: unix::Error) -> Self {
+        match err {
+            unix::Error::InvalidFileId { .. } => Self::InvalidFileId,
+            unix::Error::NoFileInfo
+            | unix::Error::InodeNotFound
+            | unix::Error::VolumeNotMounted { .. }
+            | unix::Error::Deleted => Self::NotFound,
+            unix::Error::SearchTimeout => Self::Timeout,
+            unix::Error::IdReused { path } => Self::IdReused { path },
+            unix::Error::CommandFailed { .. } => Self::Io(io::Error::other(err)),
+            unix::Error::ToolMissing { .. } => {
+                Self::Io(io::Error::new(io::ErrorKind::NotFound, err))
+            }
+            unix::Error::Unsupported { .. } => {
+                Self::Io(io::Error::new(io::ErrorKind::Unsupported, err))
+            }
+            unix::Error::ProcNotMounted => Self::Io(io::Error::new(
+                io::ErrorKind::Unsupported,
+                unix::Error::ProcNotMounted,
+            )),
+            unix::Error::Decode(err) => Self::Io(io::Error::new(io::ErrorKind::InvalidData, err)),
+            unix::Error::Open(err) | unix::Error::GetPath(err) => err.into(),
+            unix::Error::Command(err)
+            | unix::Error::Mounts(err)
+            | unix::Error::MountRoot(err)
+            | unix::Error::FileHandle(err) => Self::Io(err),
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl From<windows::Error> for Error {
+    fn from(err: windows::Error) -> Self {
+        match err {
+            windows::Error::UnsupportedIdForPlatform { .. } => Self::InvalidFileId,
+            windows::Error::IdReused { path } => Self::IdReused { path },
+            windows::Error::VolumeNotMounted { .. }
+            | windows::Error::TemporaryResourceUnsupported { .. }
+            | windows::Error::Deleted
+            | windows::Error::VolumeMismatch { .. }
+            | windows::Error::DevicePathUnresolved { .. } => Self::NotFound,
+            windows::Error::FindVolume { source, .. } | windows::Error::OpenFile { source, .. } => {
+                source.into()
+            }
+            windows::Error::VolumeHandle { source, .. } => Self::Io(source),
+            windows::Error::FileInformationByHandle(err)
+            | windows::Error::VolumePathNames(err)
+            | windows::Error::FinalPathName(err)
+            | windows::Error::FileNames(err)
+            | windows::Error::VolumeRoot(err) => Self::Io(err),
+        }
+    }
+}
+
+/// [`Error::Io`]s are equal if their [`io::ErrorKind`]s are.
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::InvalidFileId, Self::InvalidFileId)
+            | (Self::NotFound, Self::NotFound)
+            | (Self::Timeout, Self::Timeout) => true,
+            (Self::IdReused { path: a }, Self::IdReused { path: b }) => a == b,
+            (Self::Io(a), Self::Io(b)) => a.kind() == b.kind(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidFileId => write!(f, "file id variant is not supported on this platform"),
+            Self::NotFound => write!(f, "no file matches the id"),
+            Self::IdReused { path } => write!(f, "id was reused by {path:?}"),
+            Self::Timeout => write!(f, "id was not resolved before the deadline"),
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::InvalidFileId | Self::NotFound | Self::IdReused { .. } | Self::Timeout => None,
+            Self::Io(err) => Some(err),
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match err {
+            Error::Io(err) => return err,
+            Error::InvalidFileId => io::ErrorKind::InvalidInput,
+            Error::NotFound => io::ErrorKind::NotFound,
+            Error::IdReused { .. } => io::ErrorKind::Other,
+            Error::Timeout => io::ErrorKind::TimedOut,
+        };
+
+        io::Error::new(kind, err)
+    }
+}
+
+#[cfg(all(test, target_family = "unix"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_timeout_is_timeout() {
+        let err = Error::from(unix::Error::SearchTimeout);
+
+        assert_eq!(err, Error::Timeout);
+        assert_eq!(io::Error::from(err).kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -1,17 +1,280 @@
-#[cfg(target_family = "windows")]
-pub mod windows;
+use std::{
+    collections::HashMap,
+    fs, io, panic,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::Instant,
+};
+
+#[cfg(not(any(target_family = "unix", target_family = "windows")))]
+compile_error!("file_path_from_id supports only unix and windows targets");
+
+pub use file_id::FileId;
+
+mod error;
+pub use error::Error;
+
+mod cache;
+pub use cache::PathCache;
+
+mod resolution;
+pub use resolution::{Resolution, ResolveWarning};
+
+mod resolver;
+pub use resolver::{PlatformResolver, Resolver};
+
+mod snapshot;
+pub use snapshot::ErrorSnapshot;
+
+mod sync;
+
+#[cfg(feature = "serde")]
+mod serialize;
+
+#[cfg(feature = "serde")]
+pub use serialize::SerializableFileId;
 
 #[cfg(target_family = "windows")]
-pub use windows::path_from_id;
+pub mod windows;
 
 #[cfg(target_family = "windows")]
-pub use windows::Error;
+use windows as platform;
 
 #[cfg(target_family = "unix")]
 pub mod unix;
 
 #[cfg(target_family = "unix")]
-pub use unix::path_from_id;
+use unix as platform;
 
-#[cfg(target_family = "unix")]
-pub use unix::Error;
+/// Gets the id of the file or directory at `path`.
+pub fn id_from_path(path: impl AsRef<Path>) -> io::Result<FileId> {
+    file_id::get_file_id(path)
+}
+
+/// Whether ids of the variant can be resolved on this platform,
+/// without resolving the id.
+pub fn can_resolve(id: &FileId) -> bool {
+    platform::can_resolve(id)
+}
+
+/// Gets the path of a file from its id.
+#[must_use = "the id may not have resolved"]
+pub fn path_from_id(id: &FileId) -> Result<PathBuf, Error> {
+    PlatformResolver.path_from_id(id)
+}
+
+/// Gets the path of a file from its id, along with any caveats of how it was resolved.
+///
+/// Callers that need to know when a path is only a best-effort result
+/// can log the [`ResolveWarning`]s.
+#[must_use = "the id may not have resolved"]
+pub fn path_from_id_detailed(id: &FileId) -> Result<Resolution, Error> {
+    platform::path_from_id_detailed(id).map_err(Error::from)
+}
+
+/// Gets the path of a file from its id as a `String`.
+///
+/// Parts of the path that are not valid Unicode, such as unpaired UTF-16 surrogates on Windows
+/// or invalid UTF-8 on Unix, are replaced with U+FFFD, so the string may not be a usable path.
+/// Use [`path_from_id`] for the exact path.
+#[must_use = "the id may not have resolved"]
+pub fn path_from_id_lossy(id: &FileId) -> Result<String, Error> {
+    let path = path_from_id(id)?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Gets the path of a file from its id, or `None` if it could not be resolved for any reason.
+///
+/// Useful in watcher loops where events that can not be resolved are skipped.
+pub fn path_from_id_opt(id: &FileId) -> Option<PathBuf> {
+    path_from_id(id).ok()
+}
+
+/// Like [`path_from_id_opt`], but logs why the id could not be resolved
+/// when the `tracing` feature is enabled.
+pub fn path_from_id_opt_logged(id: &FileId) -> Option<PathBuf> {
+    let path = path_from_id(id);
+
+    #[cfg(feature = "tracing")]
+    if let Err(err) = &path {
+        tracing::warn!(?id, %err, "failed to resolve file id");
+    }
+
+    path.ok()
+}
+
+/// Gets the path of a file from its id, along with its metadata.
+///
+/// The metadata is read while resolving the id where possible,
+/// avoiding a second lookup of the path that could race with changes to it.
+#[must_use = "the id may not have resolved"]
+pub fn path_and_metadata_from_id(id: &FileId) -> Result<(PathBuf, fs::Metadata), Error> {
+    platform::path_and_metadata_from_id(id).map_err(Error::from)
+}
+
+/// Gets the path of a file from its id, looking near `hint` first.
+///
+/// Useful when the id is expected to be near a known path, e.g. the directory of a watcher event.
+/// Falls back to a full search if the id is not found near the hint.
+#[must_use = "the id may not have resolved"]
+pub fn path_from_id_with_hint(id: &FileId, hint: &Path) -> Result<PathBuf, Error> {
+    platform::path_from_id_with_hint(id, hint).map_err(Error::from)
+}
+
+/// Gets the path of a file from its id, checking that the path still has the id.
+///
+/// Ids are reused once a file is deleted, so the resolved path may belong to a different file
+/// by the time it is used. Returns [`Error::IdReused`] if the id of the path no longer matches.
+/// Symlinks are not followed when reading the id of the path.
+#[must_use = "the id may not have resolved"]
+pub fn path_from_id_verified(id: &FileId) -> Result<PathBuf, Error> {
+    platform::path_from_id_verified(id).map_err(Error::from)
+}
+
+/// Gets the path of a file from its id without blocking the async runtime.
+///
+/// Resolution runs on tokio's blocking thread pool.
+#[cfg(feature = "tokio")]
+#[must_use = "the id may not have resolved"]
+pub async fn path_from_id_async(id: &FileId) -> Result<PathBuf, Error> {
+    let id = *id;
+    match tokio::task::spawn_blocking(move || path_from_id(&id)).await {
+        Ok(path) => path,
+        Err(err) => Err(Error::Io(io::Error::other(err))),
+    }
+}
+
+/// Gets the root of the volume or mount a file is on, and the path of the file relative to it.
+///
+/// Relative paths stay the same when a volume is mounted at a different location.
+#[must_use = "the id may not have resolved"]
+pub fn path_from_id_relative(id: &FileId) -> Result<(PathBuf, PathBuf), Error> {
+    platform::path_from_id_relative(id).map_err(Error::from)
+}
+
+/// Gets the paths of many ids at once.
+///
+/// Results are in the same order as `ids`, with an error for each id that could not be resolved.
+/// Repeated ids are only resolved once.
+#[must_use = "the id may not have resolved"]
+pub fn path_from_ids(ids: &[FileId]) -> Vec<Result<PathBuf, Error>> {
+    let mut unique_ids = Vec::new();
+    let mut unique_indices = HashMap::new();
+    let indices = ids
+        .iter()
+        .map(|id| {
+            *unique_indices.entry(*id).or_insert_with(|| {
+                unique_ids.push(*id);
+                unique_ids.len() - 1
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let paths = platform::path_from_ids(&unique_ids)
+        .into_iter()
+        .map(|path| path.map_err(Error::from))
+        .collect::<Vec<_>>();
+
+    indices
+        .into_iter()
+        .map(|index| match &paths[index] {
+            Ok(path) => Ok(path.clone()),
+            Err(err) => Err(err.duplicate()),
+        })
+        .collect()
+}
+
+/// Gets the paths of many ids, splitting them between up to `threads` threads.
+///
+/// Results are in the same order as `ids`.
+/// Each thread resolves its ids with [`path_from_ids`],
+/// and the Windows volume cache is shared between threads.
+#[must_use = "the id may not have resolved"]
+pub fn resolve_many(ids: &[FileId], threads: usize) -> Vec<Result<PathBuf, Error>> {
+    if ids.is_empty() {
+        return Vec::new();
+    }
+
+    let threads = threads.clamp(1, ids.len());
+    let chunk_size = ids.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let handles = ids
+            .chunks(chunk_size)
+            .map(|ids| scope.spawn(move || path_from_ids(ids)))
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|err| panic::resume_unwind(err))
+            })
+            .collect()
+    })
+}
+
+/// Gets the path of a file from its id, giving up once `deadline` passes.
+///
+/// Resolution runs on a separate thread, which is left to finish in the background
+/// if the deadline passes, so a hanging volume does not block the caller.
+///
+/// # Errors
+/// + [`Error::Timeout`] if the id was not resolved before the deadline.
+#[must_use = "the id may not have resolved"]
+pub fn resolve_with_deadline(id: &FileId, deadline: Instant) -> Result<PathBuf, Error> {
+    let id = *id;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        // The receiver is gone if the deadline passed.
+        let _ = tx.send(path_from_id(&id));
+    });
+
+    match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(Error::Timeout),
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            Err(Error::Io(io::Error::other("resolution thread panicked")))
+        }
+    }
+}
+
+/// Resolves each path of a `notify` event through its id.
+///
+/// Results are in the same order as `event.paths`.
+/// Paths that no longer exist, such as the source of a completed rename, can not be resolved.
+#[cfg(feature = "notify")]
+#[must_use = "the id may not have resolved"]
+pub fn resolve_event_paths(event: &notify::Event) -> Vec<Result<PathBuf, Error>> {
+    event
+        .paths
+        .iter()
+        .map(|path| {
+            let id = id_from_path(path)?;
+            path_from_id(&id)
+        })
+        .collect()
+}
+
+/// Gets the path of an open file.
+#[must_use = "the path may not have resolved"]
+pub fn path_from_file(file: &fs::File) -> Result<PathBuf, Error> {
+    platform::path_from_file(file).map_err(Error::from)
+}
+
+// Resolvers and caches are shared between watcher threads,
+// so they must stay `Send + Sync`. Windows handles are only held while resolving.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<Error>();
+    assert_send_sync::<PathCache>();
+    assert_send_sync::<PlatformResolver>();
+
+    #[cfg(target_family = "windows")]
+    assert_send_sync::<windows::VolumeResolver>();
+
+    #[cfg(target_family = "unix")]
+    assert_send_sync::<unix::MountTable>();
+};
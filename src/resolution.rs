@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+/// Path of a resolved id, with any caveats of how it was resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resolution {
+    pub path: PathBuf,
+
+    /// Root of the volume the id was resolved through, e.g. `C:\`.
+    /// Only known on Windows.
+    pub volume_root: Option<PathBuf>,
+
+    /// Caveats of the resolution. Empty if the path was resolved as usual.
+    pub warnings: Vec<ResolveWarning>,
+}
+
+/// Caveat of a resolution that succeeded on a best-effort basis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ResolveWarning {
+    /// The file is in a case-sensitive directory, so the name it was opened with
+    /// was used instead of its normalized name.
+    OpenedName,
+
+    /// The volume has no drive letter or mount folder,
+    /// so the path is relative to the volume GUID path.
+    VolumeGuidPath,
+
+    /// The id only carries the lower 32 bits of the volume serial number,
+    /// so the volume was matched by those bits alone.
+    LowResSerial,
+}
@@ -0,0 +1,48 @@
+use crate::{platform, Error};
+use file_id::FileId;
+use std::path::PathBuf;
+
+/// Resolves file ids to paths.
+///
+/// Allows code depending on resolution to be given a different resolver,
+/// such as a mock in tests.
+pub trait Resolver {
+    fn path_from_id(&self, id: &FileId) -> Result<PathBuf, Error>;
+}
+
+impl<R: Resolver + ?Sized> Resolver for &R {
+    fn path_from_id(&self, id: &FileId) -> Result<PathBuf, Error> {
+        (**self).path_from_id(id)
+    }
+}
+
+impl<R: Resolver + ?Sized> Resolver for Box<R> {
+    fn path_from_id(&self, id: &FileId) -> Result<PathBuf, Error> {
+        (**self).path_from_id(id)
+    }
+}
+
+/// Resolver for the current platform, used by [`path_from_id`](crate::path_from_id).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlatformResolver;
+
+impl Resolver for PlatformResolver {
+    fn path_from_id(&self, id: &FileId) -> Result<PathBuf, Error> {
+        #[cfg(feature = "tracing")]
+        let start = std::time::Instant::now();
+
+        let path = platform::path_from_id(id)?;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?id, ?path, elapsed = ?start.elapsed(), "resolved file id");
+
+        Ok(path)
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl Resolver for crate::windows::VolumeResolver {
+    fn path_from_id(&self, id: &FileId) -> Result<PathBuf, Error> {
+        crate::windows::VolumeResolver::path_from_id(self, id).map_err(Error::from)
+    }
+}
@@ -0,0 +1,59 @@
+use file_id::FileId;
+use serde::{Deserialize, Serialize};
+
+/// Serializable wrapper around a [`FileId`].
+///
+/// Serialization does not depend on whether `file_id` was built with serde support.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SerializableFileId(#[serde(with = "FileIdDef")] pub FileId);
+
+impl From<FileId> for SerializableFileId {
+    fn from(id: FileId) -> Self {
+        Self(id)
+    }
+}
+
+impl From<SerializableFileId> for FileId {
+    fn from(id: SerializableFileId) -> Self {
+        id.0
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "FileId")]
+enum FileIdDef {
+    Inode {
+        device_id: u64,
+        inode_number: u64,
+    },
+    LowRes {
+        volume_serial_number: u32,
+        file_index: u64,
+    },
+    HighRes {
+        volume_serial_number: u64,
+        file_id: u128,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(id: FileId) {
+        let json = serde_json::to_string(&SerializableFileId::from(id)).unwrap();
+        let deserialized: SerializableFileId = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(FileId::from(deserialized), id);
+    }
+
+    #[test]
+    fn round_trip_high_res() {
+        round_trip(FileId::new_high_res(0x1234_5678_9abc_def0, u128::MAX - 1));
+    }
+
+    #[test]
+    fn round_trip_inode() {
+        round_trip(FileId::new_inode(66306, 1_234_567));
+    }
+}
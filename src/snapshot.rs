@@ -0,0 +1,35 @@
+use std::{fmt, io};
+
+/// Owned copy of an error, which can be cloned and serialized.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorSnapshot {
+    /// Name of the error variant, e.g. `OpenFile`.
+    pub variant: String,
+
+    /// OS error code of the underlying IO error, if any.
+    pub raw_os_error: Option<i32>,
+
+    /// Display message of the error.
+    pub message: String,
+}
+
+impl ErrorSnapshot {
+    pub(crate) fn new(
+        variant: &str,
+        err: &impl fmt::Display,
+        io_error: Option<&io::Error>,
+    ) -> Self {
+        Self {
+            variant: variant.to_string(),
+            raw_os_error: io_error.and_then(io::Error::raw_os_error),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ErrorSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
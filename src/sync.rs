@@ -0,0 +1,25 @@
+//! Lock used for the global caches.
+//!
+//! Uses `parking_lot` if the `parking_lot` feature is enabled.
+
+#[cfg(feature = "parking_lot")]
+pub(crate) use parking_lot::Mutex;
+
+#[cfg(not(feature = "parking_lot"))]
+pub(crate) use self::std_mutex::Mutex;
+
+#[cfg(not(feature = "parking_lot"))]
+mod std_mutex {
+    use std::sync::{self, MutexGuard, PoisonError};
+
+    /// [`std::sync::Mutex`] that ignores poisoning,
+    /// as the caches stay valid if a thread panics while holding the lock.
+    #[derive(Debug, Default)]
+    pub(crate) struct Mutex<T>(sync::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            self.0.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+}
@@ -1,52 +1,678 @@
+use crate::sync::Mutex;
 use file_id::FileId;
-use std::io;
-use std::path::PathBuf;
-use std::process::Command;
+use std::{
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    sync::OnceLock,
+};
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "linux")]
+use linux as os;
+
+#[cfg(target_os = "linux")]
+pub use linux::{
+    file_handle_id_from_path, path_from_file_handle_id, FileHandleId, InodeIndex, Options,
+};
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "macos")]
+use macos as os;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod unsupported;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+use unsupported as os;
+
+/// Whether ids of the variant can be resolved on this platform.
+pub fn can_resolve(id: &FileId) -> bool {
+    cfg!(any(target_os = "linux", target_os = "macos")) && matches!(id, FileId::Inode { .. })
+}
 
 pub fn path_from_id(id: &FileId) -> Result<PathBuf, Error> {
     match id {
         FileId::Inode {
             device_id,
             inode_number,
-        } => get_path_from_id(device_id, inode_number),
-        _ => Err(Error::InvalidFileId),
+        } => path_from_dev_ino(*device_id, *inode_number),
+        id => Err(Error::invalid_file_id(id)),
     }
 }
 
-fn get_path_from_id(device_id: &u64, inode_number: &u64) -> Result<PathBuf, Error> {
-    let output = match Command::new("sh")
-        .arg("-c")
-        .arg(format!("getfileinfo /.vol/{device_id}/{inode_number}"))
-        .output()
-    {
-        Ok(output) => output,
-        Err(err) => return Err(Error::Command(err)),
+/// Gets the path of a file from its device and inode number,
+/// e.g. the `st_dev` and `st_ino` of a `stat`.
+pub fn path_from_dev_ino(device_id: u64, inode_number: u64) -> Result<PathBuf, Error> {
+    os::get_path_from_id(&device_id, &inode_number)
+}
+
+/// Gets the path of a file from its id, checking that the path still has the id.
+///
+/// Inodes are reused once a file is deleted, so the resolved path may belong to a different file.
+///
+/// # Errors
+/// + [`Error::IdReused`] if the file at the resolved path has a different device or inode.
+pub fn path_from_id_verified(id: &FileId) -> Result<PathBuf, Error> {
+    use std::os::unix::fs::MetadataExt;
+
+    let path = path_from_id(id)?;
+    let metadata = match fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(err) => return Err(Error::GetPath(err)),
     };
 
-    let output = match String::from_utf8(output.stdout) {
-        Ok(output) => output,
-        Err(err) => return Err(Error::Decode(err)),
+    let FileId::Inode {
+        device_id,
+        inode_number,
+    } = id
+    else {
+        return Err(Error::invalid_file_id(id));
     };
 
-    for line in output.split("\n") {
-        let Some((key, value)) = line.split_once(":") else {
-            continue;
-        };
+    if metadata.dev() == *device_id && metadata.ino() == *inode_number {
+        Ok(path)
+    } else {
+        Err(Error::IdReused { path })
+    }
+}
+
+/// Gets the path of a file from its id, along with any caveats of how it was resolved.
+///
+/// Inodes are resolved exactly, so there are no warnings.
+pub fn path_from_id_detailed(id: &FileId) -> Result<crate::Resolution, Error> {
+    let path = path_from_id(id)?;
+    Ok(crate::Resolution {
+        path,
+        volume_root: None,
+        warnings: Vec::new(),
+    })
+}
 
-        let key = key.trim();
-        if key == "directory" || key == "file" {
-            let file = value.trim().trim_matches('"');
-            return Ok(PathBuf::from(file));
+/// Gets the path of a file from its id, along with its metadata.
+///
+/// On macOS the metadata is read from the file's volfs node rather than its path.
+/// On Linux the metadata is read from the path, and [`Error::IdReused`] is returned
+/// if the path no longer has the id.
+pub fn path_and_metadata_from_id(id: &FileId) -> Result<(PathBuf, fs::Metadata), Error> {
+    let FileId::Inode {
+        device_id,
+        inode_number,
+    } = id
+    else {
+        return Err(Error::invalid_file_id(id));
+    };
+
+    let path = path_from_dev_ino(*device_id, *inode_number)?;
+    let metadata = os::get_metadata(device_id, inode_number, &path)?;
+    Ok((path, metadata))
+}
+
+/// Gets the path of a file from its id, searching `hint` before the whole filesystem.
+///
+/// Useful when the id is expected to be near a known path,
+/// e.g. the directory of a watcher event.
+/// On macOS ids are resolved directly, so the hint is not used.
+pub fn path_from_id_with_hint(id: &FileId, hint: &Path) -> Result<PathBuf, Error> {
+    #[cfg(target_os = "linux")]
+    if let FileId::Inode {
+        device_id,
+        inode_number,
+    } = id
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if fs::metadata(hint).is_ok_and(|metadata| metadata.dev() == *device_id) {
+            let options = Options {
+                search_roots: vec![hint.to_path_buf()],
+                ..Default::default()
+            };
+
+            match linux::get_path_from_id_with(device_id, inode_number, &options) {
+                Err(Error::InodeNotFound) => {}
+                result => return result,
+            }
         }
     }
 
-    Err(Error::NoFileInfo)
+    #[cfg(not(target_os = "linux"))]
+    let _ = hint;
+
+    path_from_id(id)
+}
+
+/// Gets the path of a file from its id, using the given search options.
+#[cfg(target_os = "linux")]
+pub fn path_from_id_with(id: &FileId, options: &Options) -> Result<PathBuf, Error> {
+    match id {
+        FileId::Inode {
+            device_id,
+            inode_number,
+        } => linux::get_path_from_id_with(device_id, inode_number, options),
+        id => Err(Error::invalid_file_id(id)),
+    }
+}
+
+/// Gets the path of a file from its id, using the volfs mounted at `vol_root`
+/// instead of `/.vol`.
+///
+/// Needed for volumes whose volfs is not reachable through the system `/.vol`.
+#[cfg(target_os = "macos")]
+pub fn path_from_id_in(vol_root: &Path, id: &FileId) -> Result<PathBuf, Error> {
+    match id {
+        FileId::Inode {
+            device_id,
+            inode_number,
+        } => macos::get_path_from_id_in(vol_root, device_id, inode_number),
+        id => Err(Error::invalid_file_id(id)),
+    }
+}
+
+/// Gets the mount point of the filesystem a file is on, and the path of the file
+/// relative to it.
+pub fn path_from_id_relative(id: &FileId) -> Result<(PathBuf, PathBuf), Error> {
+    let path = path_from_id(id)?;
+    let root = os::mount_root(&path)?;
+    let relative = match path.strip_prefix(&root) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(err) => {
+            return Err(Error::MountRoot(io::Error::new(
+                io::ErrorKind::NotFound,
+                err,
+            )))
+        }
+    };
+
+    Ok((root, relative))
+}
+
+/// Gets the mount point of the filesystem on the device.
+///
+/// # Errors
+/// + [`Error::VolumeNotMounted`] if no filesystem on the device is mounted.
+///
+/// The mount table is cached between calls, and refreshed if no mount matches.
+pub fn mountpoint_for_device(device_id: u64) -> Result<PathBuf, Error> {
+    let mut mountpoints = mountpoints_for_device(device_id)?;
+    Ok(mountpoints.swap_remove(0))
+}
+
+/// Gets every mount point on the device from the process-wide mount table,
+/// refreshing the table if none match.
+///
+/// # Errors
+/// + [`Error::VolumeNotMounted`] if no filesystem on the device is mounted.
+fn mountpoints_for_device(device_id: u64) -> Result<Vec<PathBuf>, Error> {
+    let mounts = mount_table();
+    let mountpoints = mounts.lock().mountpoints_for_device(device_id);
+    if !mountpoints.is_empty() {
+        return Ok(mountpoints);
+    }
+
+    // The mount list is read without holding the lock, so other lookups are not blocked on it.
+    let table = MountTable::new()?;
+    let mountpoints = table.mountpoints_for_device(device_id);
+    *mounts.lock() = table;
+    if mountpoints.is_empty() {
+        Err(Error::VolumeNotMounted { device_id })
+    } else {
+        Ok(mountpoints)
+    }
+}
+
+/// Gets the first mount point on the device from the process-wide mount table,
+/// without refreshing the table if none match.
+///
+/// The table is only read if it has never been.
+#[cfg(target_os = "macos")]
+fn cached_mountpoint_for_device(device_id: u64) -> Option<PathBuf> {
+    let mounts = mount_table();
+    {
+        let mounts = mounts.lock();
+        if !mounts.mounts.is_empty() {
+            return mounts.mountpoints_for_device(device_id).into_iter().next();
+        }
+    }
+
+    let table = MountTable::new().ok()?;
+    let mountpoint = table.mountpoints_for_device(device_id).into_iter().next();
+    *mounts.lock() = table;
+    mountpoint
+}
+
+/// Process-wide mount table.
+fn mount_table() -> &'static Mutex<MountTable> {
+    static MOUNTS: OnceLock<Mutex<MountTable>> = OnceLock::new();
+    MOUNTS.get_or_init(Default::default)
+}
+
+/// Mount points and the devices they are on.
+///
+/// Reading the mount list requires parsing `/proc/self/mountinfo` or calling `getmntinfo`,
+/// so reusing a table avoids repeating it for each lookup.
+/// Devices are taken from the mount list, so mount points are never accessed.
+#[derive(Debug, Default)]
+pub struct MountTable {
+    mounts: Vec<(PathBuf, u64)>,
+}
+
+impl MountTable {
+    /// Creates a table of the currently mounted filesystems.
+    pub fn new() -> Result<Self, Error> {
+        let mut table = Self::default();
+        table.refresh()?;
+        Ok(table)
+    }
+
+    /// Rereads the mount list.
+    /// Should be called when a filesystem is mounted or unmounted.
+    pub fn refresh(&mut self) -> Result<(), Error> {
+        self.mounts = os::get_mounts()?;
+        Ok(())
+    }
+
+    /// Gets the first mount point on the device.
+    ///
+    /// # Errors
+    /// + [`Error::VolumeNotMounted`] if no mount point is on the device.
+    pub fn mountpoint_for_device(&self, device_id: u64) -> Result<PathBuf, Error> {
+        self.mounts
+            .iter()
+            .find(|(_, mount_device_id)| *mount_device_id == device_id)
+            .map(|(mountpoint, _)| mountpoint.clone())
+            .ok_or(Error::VolumeNotMounted { device_id })
+    }
+
+    /// Gets every mount point on the device, in mount order.
+    fn mountpoints_for_device(&self, device_id: u64) -> Vec<PathBuf> {
+        self.mounts
+            .iter()
+            .filter(|(_, mount_device_id)| *mount_device_id == device_id)
+            .map(|(mountpoint, _)| mountpoint.clone())
+            .collect()
+    }
+
+    /// Iterates over the mount points and the devices they are on.
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, u64)> {
+        self.mounts
+            .iter()
+            .map(|(mountpoint, device_id)| (mountpoint.as_path(), *device_id))
+    }
+}
+
+/// Gets the paths of many ids at once.
+///
+/// Results are in the same order as `ids`.
+pub fn path_from_ids(ids: &[FileId]) -> Vec<Result<PathBuf, Error>> {
+    os::path_from_ids(ids)
+}
+
+/// Gets the path of an open file.
+pub fn path_from_file(file: &fs::File) -> Result<PathBuf, Error> {
+    os::path_from_file(file)
 }
 
 #[derive(Debug)]
 pub enum Error {
-    InvalidFileId,
+    /// The file id is not a [`FileId::Inode`].
+    InvalidFileId {
+        expected: &'static str,
+        got: &'static str,
+    },
     Command(io::Error),
+
+    /// A command line tool needed to resolve the id is not installed.
+    ToolMissing {
+        tool: &'static str,
+    },
+
+    /// `getfileinfo` exited unsuccessfully.
+    CommandFailed {
+        status: ExitStatus,
+        stderr: String,
+    },
+
     Decode(std::string::FromUtf8Error),
     NoFileInfo,
+
+    /// Could not open a file, e.g. the `/.vol` node of the inode.
+    Open(io::Error),
+
+    /// Could not get the path of an open file.
+    GetPath(io::Error),
+
+    /// Could not read the mount table.
+    Mounts(io::Error),
+
+    /// The inode was not found under any mounted filesystem of its device.
+    InodeNotFound,
+
+    /// No mounted filesystem is on the device.
+    VolumeNotMounted {
+        device_id: u64,
+    },
+
+    /// The open file has been deleted.
+    Deleted,
+
+    /// `/proc` is not mounted, so open files can not be resolved.
+    ProcNotMounted,
+
+    /// The inode was not found before the search timed out.
+    SearchTimeout,
+
+    /// Could not get the mount point of a path.
+    MountRoot(io::Error),
+
+    /// Could not get a file handle, or open a file from its handle.
+    FileHandle(io::Error),
+
+    /// The resolved path belongs to a different file than the id.
+    IdReused {
+        path: PathBuf,
+    },
+
+    /// Ids can not be resolved on this operating system.
+    Unsupported {
+        platform: &'static str,
+    },
+}
+
+impl Error {
+    /// Gets the underlying [`io::Error`], if the error wraps one.
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            Self::Command(err)
+            | Self::Open(err)
+            | Self::GetPath(err)
+            | Self::Mounts(err)
+            | Self::MountRoot(err)
+            | Self::FileHandle(err) => Some(err),
+            Self::InvalidFileId { .. }
+            | Self::ToolMissing { .. }
+            | Self::CommandFailed { .. }
+            | Self::Decode(_)
+            | Self::NoFileInfo
+            | Self::InodeNotFound
+            | Self::VolumeNotMounted { .. }
+            | Self::Deleted
+            | Self::ProcNotMounted
+            | Self::SearchTimeout
+            | Self::IdReused { .. }
+            | Self::Unsupported { .. } => None,
+        }
+    }
+
+    /// Gets the OS error code of the underlying [`io::Error`], if any.
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.io_error().and_then(io::Error::raw_os_error)
+    }
+
+    /// Whether the id no longer maps to a live file,
+    /// e.g. because the file was deleted or its filesystem unmounted.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Self::NoFileInfo
+            | Self::InodeNotFound
+            | Self::VolumeNotMounted { .. }
+            | Self::Deleted => true,
+            Self::Open(err) | Self::GetPath(err) => err.kind() == io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+
+    /// Captures the error as an owned value that can be cloned.
+    pub fn snapshot(&self) -> crate::ErrorSnapshot {
+        let variant = match self {
+            Self::InvalidFileId { .. } => "InvalidFileId",
+            Self::Command(_) => "Command",
+            Self::ToolMissing { .. } => "ToolMissing",
+            Self::CommandFailed { .. } => "CommandFailed",
+            Self::Decode(_) => "Decode",
+            Self::NoFileInfo => "NoFileInfo",
+            Self::Open(_) => "Open",
+            Self::GetPath(_) => "GetPath",
+            Self::Mounts(_) => "Mounts",
+            Self::InodeNotFound => "InodeNotFound",
+            Self::VolumeNotMounted { .. } => "VolumeNotMounted",
+            Self::Deleted => "Deleted",
+            Self::ProcNotMounted => "ProcNotMounted",
+            Self::SearchTimeout => "SearchTimeout",
+            Self::MountRoot(_) => "MountRoot",
+            Self::FileHandle(_) => "FileHandle",
+            Self::IdReused { .. } => "IdReused",
+            Self::Unsupported { .. } => "Unsupported",
+        };
+
+        crate::ErrorSnapshot::new(variant, self, self.io_error())
+    }
+
+    /// Copies the error for another result.
+    /// IO errors keep their OS error code, or their kind and message.
+    #[cfg(target_os = "linux")]
+    fn duplicate(&self) -> Self {
+        let io = |err: &io::Error| match err.raw_os_error() {
+            Some(code) => io::Error::from_raw_os_error(code),
+            None => io::Error::new(err.kind(), err.to_string()),
+        };
+
+        match self {
+            Self::InvalidFileId { expected, got } => Self::InvalidFileId { expected, got },
+            Self::Command(err) => Self::Command(io(err)),
+            Self::ToolMissing { tool } => Self::ToolMissing { tool },
+            Self::CommandFailed { status, stderr } => Self::CommandFailed {
+                status: *status,
+                stderr: stderr.clone(),
+            },
+            Self::Decode(err) => Self::Decode(err.clone()),
+            Self::NoFileInfo => Self::NoFileInfo,
+            Self::Open(err) => Self::Open(io(err)),
+            Self::GetPath(err) => Self::GetPath(io(err)),
+            Self::Mounts(err) => Self::Mounts(io(err)),
+            Self::InodeNotFound => Self::InodeNotFound,
+            Self::VolumeNotMounted { device_id } => Self::VolumeNotMounted {
+                device_id: *device_id,
+            },
+            Self::Deleted => Self::Deleted,
+            Self::ProcNotMounted => Self::ProcNotMounted,
+            Self::SearchTimeout => Self::SearchTimeout,
+            Self::MountRoot(err) => Self::MountRoot(io(err)),
+            Self::FileHandle(err) => Self::FileHandle(io(err)),
+            Self::IdReused { path } => Self::IdReused { path: path.clone() },
+            Self::Unsupported { platform } => Self::Unsupported { platform },
+        }
+    }
+
+    fn invalid_file_id(id: &FileId) -> Self {
+        let got = match id {
+            FileId::Inode { .. } => "Inode",
+            FileId::LowRes { .. } => "LowRes",
+            FileId::HighRes { .. } => "HighRes",
+        };
+
+        Self::InvalidFileId {
+            expected: "Inode",
+            got,
+        }
+    }
+}
+
+/// Errors wrapping an [`io::Error`] are equal if their [`io::ErrorKind`]s are.
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::InvalidFileId {
+                    expected: a_expected,
+                    got: a_got,
+                },
+                Self::InvalidFileId {
+                    expected: b_expected,
+                    got: b_got,
+                },
+            ) => a_expected == b_expected && a_got == b_got,
+            (Self::NoFileInfo, Self::NoFileInfo)
+            | (Self::InodeNotFound, Self::InodeNotFound)
+            | (Self::Deleted, Self::Deleted)
+            | (Self::ProcNotMounted, Self::ProcNotMounted)
+            | (Self::SearchTimeout, Self::SearchTimeout) => true,
+            (Self::Decode(a), Self::Decode(b)) => a == b,
+            (Self::Command(a), Self::Command(b))
+            | (Self::Open(a), Self::Open(b))
+            | (Self::GetPath(a), Self::GetPath(b))
+            | (Self::Mounts(a), Self::Mounts(b))
+            | (Self::MountRoot(a), Self::MountRoot(b))
+            | (Self::FileHandle(a), Self::FileHandle(b)) => a.kind() == b.kind(),
+            (Self::VolumeNotMounted { device_id: a }, Self::VolumeNotMounted { device_id: b }) => {
+                a == b
+            }
+            (Self::IdReused { path: a }, Self::IdReused { path: b }) => a == b,
+            (Self::Unsupported { platform: a }, Self::Unsupported { platform: b })
+            | (Self::ToolMissing { tool: a }, Self::ToolMissing { tool: b }) => a == b,
+            (
+                Self::CommandFailed {
+                    status: a_status,
+                    stderr: a_stderr,
+                },
+                Self::CommandFailed {
+                    status: b_status,
+                    stderr: b_stderr,
+                },
+            ) => a_status == b_status && a_stderr == b_stderr,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidFileId { expected, got } => {
+                write!(f, "expected file id variant {expected}, got {got}")
+            }
+            Self::Command(err) => write!(f, "failed to run getfileinfo: {err}"),
+            Self::ToolMissing { tool } => write!(
+                f,
+                "{tool} is not installed, install the Xcode command line tools"
+            ),
+            Self::CommandFailed { status, stderr } => {
+                write!(f, "getfileinfo failed with {status}: {stderr}")
+            }
+            Self::Decode(err) => write!(f, "could not decode getfileinfo output: {err}"),
+            Self::NoFileInfo => write!(f, "getfileinfo output did not contain a path"),
+            Self::Open(err) => write!(f, "failed to open volfs node: {err}"),
+            Self::GetPath(err) => write!(f, "failed to get path of file descriptor: {err}"),
+            Self::Mounts(err) => write!(f, "failed to read mount table: {err}"),
+            Self::InodeNotFound => write!(f, "inode not found under any mounted filesystem"),
+            Self::VolumeNotMounted { device_id } => {
+                write!(f, "no mounted filesystem on device {device_id}")
+            }
+            Self::Deleted => write!(f, "file has been deleted"),
+            Self::ProcNotMounted => write!(f, "/proc is not mounted"),
+            Self::SearchTimeout => write!(f, "timed out searching for inode"),
+            Self::MountRoot(err) => write!(f, "failed to get mount point: {err}"),
+            Self::FileHandle(err) => write!(f, "failed to use file handle: {err}"),
+            Self::IdReused { path } => {
+                write!(f, "{path:?} belongs to a different file than the id")
+            }
+            Self::Unsupported { platform } => {
+                write!(f, "resolving file ids is not supported on {platform}")
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::InvalidFileId { .. }
+            | Self::ToolMissing { .. }
+            | Self::CommandFailed { .. }
+            | Self::NoFileInfo
+            | Self::InodeNotFound
+            | Self::VolumeNotMounted { .. }
+            | Self::Deleted
+            | Self::ProcNotMounted
+            | Self::SearchTimeout
+            | Self::IdReused { .. }
+            | Self::Unsupported { .. } => None,
+            Self::Decode(err) => Some(err),
+            Self::Command(err)
+            | Self::Open(err)
+            | Self::GetPath(err)
+            | Self::Mounts(err)
+            | Self::MountRoot(err)
+            | Self::FileHandle(err) => Some(err),
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match err {
+            Error::Command(err)
+            | Error::Open(err)
+            | Error::GetPath(err)
+            | Error::Mounts(err)
+            | Error::MountRoot(err)
+            | Error::FileHandle(err) => return err,
+            Error::InvalidFileId { .. } => io::ErrorKind::InvalidInput,
+            Error::NoFileInfo
+            | Error::InodeNotFound
+            | Error::VolumeNotMounted { .. }
+            | Error::Deleted
+            | Error::ToolMissing { .. } => io::ErrorKind::NotFound,
+            Error::Decode(_) => io::ErrorKind::InvalidData,
+            Error::ProcNotMounted | Error::Unsupported { .. } => io::ErrorKind::Unsupported,
+            Error::SearchTimeout => io::ErrorKind::TimedOut,
+            Error::CommandFailed { .. } | Error::IdReused { .. } => io::ErrorKind::Other,
+        };
+
+        io::Error::new(kind, err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn display() {
+        let err = Error::invalid_file_id(&FileId::new_high_res(1, 2));
+
+        assert_eq!(
+            err.to_string(),
+            "expected file id variant Inode, got HighRes"
+        );
+    }
+
+    #[test]
+    fn snapshot_names_variant() {
+        let snapshot = Error::GetPath(io::Error::from_raw_os_error(libc::ENOENT)).snapshot();
+
+        assert_eq!(snapshot.variant, "GetPath");
+        assert_eq!(snapshot.raw_os_error, Some(libc::ENOENT));
+        assert_eq!(Error::SearchTimeout.snapshot().variant, "SearchTimeout");
+    }
+
+    #[test]
+    fn source_of_io_errors() {
+        let err = Error::GetPath(io::Error::from(io::ErrorKind::NotFound));
+        let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+
+        assert_eq!(source.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn no_source_without_io_error() {
+        let err = Error::invalid_file_id(&FileId::new_low_res(1, 2));
+
+        assert!(err.source().is_none());
+        assert!(Error::NoFileInfo.source().is_none());
+    }
 }
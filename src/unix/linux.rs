@@ -0,0 +1,757 @@
+use super::Error;
+use file_id::FileId;
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    fs, io,
+    os::unix::{
+        ffi::OsStringExt,
+        fs::{DirEntryExt, MetadataExt},
+        io::AsRawFd,
+    },
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Options for searching for an inode.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Directories to search instead of the mount points of the device.
+    /// Roots on other devices are skipped.
+    pub search_roots: Vec<PathBuf>,
+
+    /// Give up with [`Error::SearchTimeout`] if the inode is not found in time.
+    pub timeout: Option<Duration>,
+}
+
+/// Paths of the inodes under a root, for resolving many inodes without searching for each.
+///
+/// The root is walked once when the index is created, without leaving its device.
+/// Keep the index up to date with [`insert`](Self::insert) and [`remove`](Self::remove)
+/// as the tree changes.
+#[derive(Debug)]
+pub struct InodeIndex {
+    device_id: u64,
+    paths: HashMap<u64, Vec<PathBuf>>,
+}
+
+impl InodeIndex {
+    /// Indexes every file under `root`.
+    pub fn new(root: &Path) -> Result<Self, Error> {
+        let device_id = match fs::metadata(root) {
+            Ok(metadata) => metadata.dev(),
+            Err(err) => return Err(Error::Open(err)),
+        };
+
+        let mut index = Self {
+            device_id,
+            paths: HashMap::new(),
+        };
+
+        index.insert(root)?;
+        Ok(index)
+    }
+
+    /// Id of the device the index is on.
+    pub fn device_id(&self) -> u64 {
+        self.device_id
+    }
+
+    /// Gets the indexed paths of the inode, one for each of its hard links.
+    pub fn paths_for_inode(&self, inode_number: u64) -> &[PathBuf] {
+        self.paths
+            .get(&inode_number)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Adds the file at `path` to the index, along with everything under it if it is a directory.
+    ///
+    /// Files on other devices are skipped.
+    pub fn insert(&mut self, path: &Path) -> Result<(), Error> {
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(err) => return Err(Error::Open(err)),
+        };
+
+        if metadata.dev() != self.device_id {
+            return Ok(());
+        }
+
+        self.add(metadata.ino(), path.to_path_buf());
+        if !metadata.is_dir() {
+            return Ok(());
+        }
+
+        let mut dirs = vec![path.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                    match fs::symlink_metadata(&path) {
+                        Ok(metadata) if metadata.dev() == self.device_id => {
+                            self.add(metadata.ino(), path.clone());
+                            dirs.push(path);
+                        }
+                        _ => {}
+                    }
+                } else {
+                    self.add(entry.ino(), path);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes `path`, and everything under it, from the index.
+    pub fn remove(&mut self, path: &Path) {
+        self.paths.retain(|_, paths| {
+            paths.retain(|indexed| !indexed.starts_with(path));
+            !paths.is_empty()
+        });
+    }
+
+    /// Gets the path of a file from its id, searching its device if the index has no
+    /// current path for it.
+    pub fn path_from_id(&self, id: &FileId) -> Result<PathBuf, Error> {
+        let FileId::Inode {
+            device_id,
+            inode_number,
+        } = id
+        else {
+            return Err(Error::invalid_file_id(id));
+        };
+
+        if *device_id == self.device_id {
+            let indexed = self.paths_for_inode(*inode_number).iter().find(|path| {
+                fs::symlink_metadata(path).is_ok_and(|metadata| {
+                    metadata.dev() == *device_id && metadata.ino() == *inode_number
+                })
+            });
+
+            if let Some(path) = indexed {
+                return Ok(path.clone());
+            }
+        }
+
+        get_path_from_id(device_id, inode_number)
+    }
+
+    fn add(&mut self, inode_number: u64, path: PathBuf) {
+        let paths = self.paths.entry(inode_number).or_default();
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+}
+
+/// Id of a file as the opaque handle given by `name_to_handle_at`.
+///
+/// Unlike an inode, a handle is resolved by opening the file directly instead of
+/// searching its filesystem, but opening it requires the `CAP_DAC_READ_SEARCH` capability.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FileHandleId {
+    device_id: u64,
+    handle_type: i32,
+    handle: Vec<u8>,
+}
+
+impl FileHandleId {
+    /// Rebuilds an id from the parts given by its accessors, e.g. after loading it from disk.
+    ///
+    /// Returns `None` if `handle` is longer than `MAX_HANDLE_SZ` bytes.
+    pub fn from_raw_parts(device_id: u64, handle_type: i32, handle: Vec<u8>) -> Option<Self> {
+        if handle.len() > libc::MAX_HANDLE_SZ as usize {
+            return None;
+        }
+
+        Some(Self {
+            device_id,
+            handle_type,
+            handle,
+        })
+    }
+
+    /// Id of the device the file is on.
+    pub fn device_id(&self) -> u64 {
+        self.device_id
+    }
+
+    /// Filesystem specific type of the handle.
+    pub fn handle_type(&self) -> i32 {
+        self.handle_type
+    }
+
+    /// Opaque bytes of the handle.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.handle
+    }
+}
+
+/// Buffer for a `file_handle` with room for the largest handle.
+#[repr(C)]
+struct FileHandleBuf {
+    header: libc::file_handle,
+    handle: [u8; libc::MAX_HANDLE_SZ as usize],
+}
+
+/// Gets the file handle id of the file at `path`.
+pub fn file_handle_id_from_path(path: &Path) -> Result<FileHandleId, Error> {
+    use std::{ffi::CString, mem, os::unix::ffi::OsStrExt};
+
+    let device_id = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata.dev(),
+        Err(err) => return Err(Error::FileHandle(err)),
+    };
+
+    let c_path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(c_path) => c_path,
+        Err(err) => {
+            return Err(Error::FileHandle(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                err,
+            )))
+        }
+    };
+
+    let mut buf: FileHandleBuf = unsafe { mem::zeroed() };
+    buf.header.handle_bytes = libc::MAX_HANDLE_SZ as u32;
+    let mut mount_id = 0;
+    let ret = unsafe {
+        libc::name_to_handle_at(
+            libc::AT_FDCWD,
+            c_path.as_ptr(),
+            &mut buf.header,
+            &mut mount_id,
+            0,
+        )
+    };
+
+    if ret != 0 {
+        return Err(Error::FileHandle(io::Error::last_os_error()));
+    }
+
+    Ok(FileHandleId {
+        device_id,
+        handle_type: buf.header.handle_type,
+        handle: buf.handle[..buf.header.handle_bytes as usize].to_vec(),
+    })
+}
+
+/// Gets the path of a file from its handle id, opening it with `open_by_handle_at`.
+///
+/// # Errors
+/// + [`Error::InodeNotFound`] if the file no longer exists.
+/// + [`Error::FileHandle`] if the file could not be opened,
+///   e.g. because the process lacks `CAP_DAC_READ_SEARCH`.
+pub fn path_from_file_handle_id(id: &FileHandleId) -> Result<PathBuf, Error> {
+    use std::{mem, os::fd::FromRawFd};
+
+    let mountpoint = super::mountpoint_for_device(id.device_id)?;
+    let mount = match fs::File::open(mountpoint) {
+        Ok(mount) => mount,
+        Err(err) => return Err(Error::Open(err)),
+    };
+
+    let mut buf: FileHandleBuf = unsafe { mem::zeroed() };
+    buf.header.handle_bytes = id.handle.len() as u32;
+    buf.header.handle_type = id.handle_type;
+    buf.handle[..id.handle.len()].copy_from_slice(&id.handle);
+
+    let fd = unsafe { libc::open_by_handle_at(mount.as_raw_fd(), &mut buf.header, libc::O_PATH) };
+    if fd == -1 {
+        let err = io::Error::last_os_error();
+        return match err.raw_os_error() {
+            Some(libc::ESTALE) => Err(Error::InodeNotFound),
+            _ => Err(Error::FileHandle(err)),
+        };
+    }
+
+    let file = unsafe { fs::File::from_raw_fd(fd) };
+    path_from_file(&file)
+}
+
+/// Gets the path of an open file.
+///
+/// # Errors
+/// + [`Error::Deleted`] if the file has been unlinked.
+/// + [`Error::ProcNotMounted`] if `/proc` is not available.
+pub fn path_from_file(file: &fs::File) -> Result<PathBuf, Error> {
+    let path = match fs::read_link(format!("/proc/self/fd/{}", file.as_raw_fd())) {
+        Ok(path) => path,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            if Path::new("/proc/self").exists() {
+                return Err(Error::GetPath(err));
+            } else {
+                return Err(Error::ProcNotMounted);
+            }
+        }
+        Err(err) => return Err(Error::GetPath(err)),
+    };
+
+    // Unlinked files are reported as `<path> (deleted)`.
+    match file.metadata() {
+        Ok(metadata) if metadata.nlink() == 0 => Err(Error::Deleted),
+        Ok(_) => Ok(path),
+        Err(err) => Err(Error::GetPath(err)),
+    }
+}
+
+/// Resolves the ids, scanning each device only once.
+pub fn path_from_ids(ids: &[FileId]) -> Vec<Result<PathBuf, Error>> {
+    let mut inodes_by_device = HashMap::<u64, HashSet<u64>>::new();
+    for id in ids {
+        if let FileId::Inode {
+            device_id,
+            inode_number,
+        } = id
+        {
+            inodes_by_device
+                .entry(*device_id)
+                .or_default()
+                .insert(*inode_number);
+        }
+    }
+
+    let paths = inodes_by_device
+        .into_iter()
+        .map(|(device_id, inodes)| {
+            let paths = match super::mountpoints_for_device(device_id) {
+                Ok(mountpoints) => find_inodes(&mountpoints, device_id, inodes, None),
+                Err(Error::VolumeNotMounted { .. }) => Ok(None),
+                Err(err) => Err(err),
+            };
+            (device_id, paths)
+        })
+        .collect::<HashMap<_, _>>();
+
+    ids.iter()
+        .map(|id| match id {
+            FileId::Inode {
+                device_id,
+                inode_number,
+            } => match paths.get(device_id) {
+                Some(Ok(Some(paths))) => {
+                    paths.get(inode_number).cloned().ok_or(Error::InodeNotFound)
+                }
+                Some(Err(err)) => Err(err.duplicate()),
+                _ => Err(Error::VolumeNotMounted {
+                    device_id: *device_id,
+                }),
+            },
+            id => Err(Error::invalid_file_id(id)),
+        })
+        .collect()
+}
+
+/// Searches the filesystems mounted from the device for the inode.
+pub fn get_path_from_id(device_id: &u64, inode_number: &u64) -> Result<PathBuf, Error> {
+    get_path_from_id_with(device_id, inode_number, &Options::default())
+}
+
+/// Searches for the inode using the given options.
+pub fn get_path_from_id_with(
+    device_id: &u64,
+    inode_number: &u64,
+    options: &Options,
+) -> Result<PathBuf, Error> {
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+    let roots = if options.search_roots.is_empty() {
+        super::mountpoints_for_device(*device_id)?
+    } else {
+        options.search_roots.clone()
+    };
+
+    let Some(mut paths) =
+        find_inodes(&roots, *device_id, HashSet::from([*inode_number]), deadline)?
+    else {
+        return Err(Error::VolumeNotMounted {
+            device_id: *device_id,
+        });
+    };
+
+    paths.remove(inode_number).ok_or(Error::InodeNotFound)
+}
+
+/// Gets the metadata of the inode at its resolved `path`.
+///
+/// # Errors
+/// + [`Error::IdReused`] if the file at `path` is no longer the inode.
+pub fn get_metadata(
+    device_id: &u64,
+    inode_number: &u64,
+    path: &Path,
+) -> Result<fs::Metadata, Error> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) => return Err(Error::GetPath(err)),
+    };
+
+    if metadata.dev() == *device_id && metadata.ino() == *inode_number {
+        Ok(metadata)
+    } else {
+        Err(Error::IdReused {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+/// Gets the mount point of the filesystem `path` is on.
+pub fn mount_root(path: &Path) -> Result<PathBuf, Error> {
+    let device_id = match fs::metadata(path) {
+        Ok(metadata) => metadata.dev(),
+        Err(err) => return Err(Error::MountRoot(err)),
+    };
+
+    super::mountpoints_for_device(device_id)?
+        .into_iter()
+        .filter(|mountpoint| path.starts_with(mountpoint))
+        .max_by_key(|mountpoint| mountpoint.components().count())
+        .ok_or(Error::VolumeNotMounted { device_id })
+}
+
+/// Gets the mount points listed in `/proc/self/mountinfo`, with the devices they are on.
+pub fn get_mounts() -> Result<Vec<(PathBuf, u64)>, Error> {
+    let mounts = match fs::read_to_string("/proc/self/mountinfo") {
+        Ok(mounts) => mounts,
+        Err(err) => return Err(Error::Mounts(err)),
+    };
+
+    // A later mount at the same mount point hides the earlier ones.
+    let mut seen = HashSet::new();
+    let mut mounts = mounts
+        .lines()
+        .rev()
+        .filter_map(parse_mountinfo_line)
+        .filter(|(mountpoint, _)| seen.insert(mountpoint.clone()))
+        .collect::<Vec<_>>();
+
+    mounts.reverse();
+    Ok(mounts)
+}
+
+/// Gets the mount point and device of a `/proc/self/mountinfo` line,
+/// e.g. `36 35 98:0 /mnt1 /mnt/parent rw,noatime - ext3 /dev/root rw`.
+fn parse_mountinfo_line(line: &str) -> Option<(PathBuf, u64)> {
+    let mut fields = line.split_whitespace();
+    let (major, minor) = fields.nth(2)?.split_once(':')?;
+    let mountpoint = fields.nth(1)?;
+    let device_id = libc::makedev(major.parse().ok()?, minor.parse().ok()?);
+    Some((PathBuf::from(unescape_mount_field(mountpoint)), device_id))
+}
+
+/// Decodes the octal escapes (e.g. `\040` for a space) used in mount list fields.
+fn unescape_mount_field(field: &str) -> OsString {
+    let bytes = field.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'\\' {
+            if let Some(code) = bytes.get(idx + 1..idx + 4) {
+                if code.iter().all(|c| (b'0'..=b'7').contains(c)) {
+                    unescaped.push(code.iter().fold(0, |acc, c| (acc << 3) | (c - b'0')));
+                    idx += 4;
+                    continue;
+                }
+            }
+        }
+
+        unescaped.push(bytes[idx]);
+        idx += 1;
+    }
+
+    OsString::from_vec(unescaped)
+}
+
+/// Searches the roots on the device for the inodes.
+/// Returns `None` if none of the roots are on the device.
+fn find_inodes(
+    roots: &[PathBuf],
+    device_id: u64,
+    mut inodes: HashSet<u64>,
+    deadline: Option<Instant>,
+) -> Result<Option<HashMap<u64, PathBuf>>, Error> {
+    let mut paths = HashMap::with_capacity(inodes.len());
+    let mut mounted = false;
+    for mountpoint in roots {
+        if inodes.is_empty() {
+            break;
+        }
+
+        let Ok(metadata) = fs::metadata(mountpoint) else {
+            continue;
+        };
+
+        if metadata.dev() != device_id {
+            continue;
+        }
+
+        mounted = true;
+        if inodes.remove(&metadata.ino()) {
+            paths.insert(metadata.ino(), mountpoint.clone());
+        }
+
+        walk_inodes(mountpoint, device_id, &mut inodes, &mut paths, deadline)?;
+    }
+
+    Ok(mounted.then_some(paths))
+}
+
+/// Walks the tree under `root` looking for the inodes, without leaving the device.
+/// Found inodes are moved from `inodes` into `paths`.
+fn walk_inodes(
+    root: &Path,
+    device_id: u64,
+    inodes: &mut HashSet<u64>,
+    paths: &mut HashMap<u64, PathBuf>,
+    deadline: Option<Instant>,
+) -> Result<(), Error> {
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        if inodes.is_empty() {
+            return Ok(());
+        }
+
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(Error::SearchTimeout);
+        }
+
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            if inodes.contains(&entry.ino()) {
+                let path = entry.path();
+                if let Ok(metadata) = fs::symlink_metadata(&path) {
+                    if metadata.dev() == device_id && inodes.remove(&metadata.ino()) {
+                        paths.insert(metadata.ino(), path);
+                    }
+                }
+            }
+
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+
+            if file_type.is_dir() {
+                let path = entry.path();
+                match fs::symlink_metadata(&path) {
+                    Ok(metadata) if metadata.dev() == device_id => dirs.push(path),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mountinfo_line_reads_device_and_mountpoint() {
+        let line = "36 35 98:3 /mnt1 /mnt/with\\040space rw,noatime master:1 - ext3 /dev/root rw";
+
+        assert_eq!(
+            parse_mountinfo_line(line),
+            Some((PathBuf::from("/mnt/with space"), libc::makedev(98, 3)))
+        );
+        assert_eq!(parse_mountinfo_line("36 35"), None);
+    }
+
+    #[test]
+    fn duplicated_errors_keep_variant_and_os_error() {
+        let err = Error::Mounts(io::Error::from_raw_os_error(libc::EACCES));
+        let duplicate = err.duplicate();
+
+        assert_eq!(duplicate.snapshot().variant, "Mounts");
+        assert_eq!(duplicate.raw_os_error(), Some(libc::EACCES));
+        assert_eq!(
+            Error::VolumeNotMounted { device_id: 3 }.duplicate(),
+            Error::VolumeNotMounted { device_id: 3 }
+        );
+    }
+
+    #[test]
+    fn unescape_mount_field_decodes_octal_escapes() {
+        assert_eq!(
+            unescape_mount_field(r"/mnt/with\040space"),
+            "/mnt/with space"
+        );
+        assert_eq!(unescape_mount_field(r"/mnt/with\011tab"), "/mnt/with\ttab");
+        assert_eq!(
+            unescape_mount_field(r"/mnt/back\134slash"),
+            r"/mnt/back\slash"
+        );
+        assert_eq!(unescape_mount_field(r"/mnt/plain"), "/mnt/plain");
+    }
+
+    #[test]
+    fn unescape_mount_field_keeps_incomplete_escapes() {
+        assert_eq!(unescape_mount_field(r"/mnt/a\\b"), r"/mnt/a\\b");
+        assert_eq!(unescape_mount_field(r"/mnt/a\08"), r"/mnt/a\08");
+        assert_eq!(unescape_mount_field(r"/mnt/a\04"), r"/mnt/a\04");
+    }
+
+    /// Creates an empty directory under the temporary directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("file_path_from_id-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn inode_of(path: &Path) -> u64 {
+        fs::symlink_metadata(path).unwrap().ino()
+    }
+
+    #[test]
+    fn inode_index_finds_files_under_root() {
+        let root = temp_dir("index-new");
+        let file = root.join("dir").join("file.txt");
+        fs::create_dir(root.join("dir")).unwrap();
+        fs::write(&file, "indexed").unwrap();
+
+        let index = InodeIndex::new(&root).unwrap();
+        let id = crate::id_from_path(&file).unwrap();
+        let paths = index.paths_for_inode(inode_of(&file)).to_vec();
+        let dir_paths = index.paths_for_inode(inode_of(&root.join("dir"))).to_vec();
+        let resolved = index.path_from_id(&id);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(paths, vec![file.clone()]);
+        assert_eq!(dir_paths, vec![root.join("dir")]);
+        assert_eq!(resolved.unwrap(), file);
+    }
+
+    #[test]
+    fn inode_index_inserts_hard_links_once() {
+        let root = temp_dir("index-insert");
+        let mut index = InodeIndex::new(&root).unwrap();
+
+        let file = root.join("file.txt");
+        let link = root.join("link.txt");
+        fs::write(&file, "linked").unwrap();
+        fs::hard_link(&file, &link).unwrap();
+        index.insert(&file).unwrap();
+        index.insert(&link).unwrap();
+        index.insert(&file).unwrap();
+
+        let paths = index.paths_for_inode(inode_of(&file)).to_vec();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(paths, vec![file, link]);
+    }
+
+    #[test]
+    fn inode_index_follows_renames() {
+        let root = temp_dir("index-rename");
+        fs::create_dir(root.join("old")).unwrap();
+        fs::write(root.join("old").join("file.txt"), "renamed").unwrap();
+        let mut index = InodeIndex::new(&root).unwrap();
+        let inode = inode_of(&root.join("old").join("file.txt"));
+
+        fs::rename(root.join("old"), root.join("new")).unwrap();
+        index.remove(&root.join("old"));
+        index.insert(&root.join("new")).unwrap();
+
+        let paths = index.paths_for_inode(inode).to_vec();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(paths, vec![root.join("new").join("file.txt")]);
+    }
+
+    #[test]
+    fn inode_index_skips_stale_paths() {
+        let root = temp_dir("index-stale");
+        let old = root.join("old.txt");
+        let new = root.join("new.txt");
+        fs::write(&old, "moved").unwrap();
+        let mut index = InodeIndex::new(&root).unwrap();
+        let id = crate::id_from_path(&old).unwrap();
+
+        // The old path now belongs to another file, and is still indexed for the id.
+        fs::rename(&old, &new).unwrap();
+        fs::write(&old, "replacement").unwrap();
+        index.insert(&new).unwrap();
+
+        let paths = index.paths_for_inode(inode_of(&new)).to_vec();
+        let resolved = index.path_from_id(&id);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(paths, vec![old, new.clone()]);
+        assert_eq!(resolved.unwrap(), new);
+    }
+
+    #[test]
+    fn inode_index_removes_trees() {
+        let root = temp_dir("index-remove");
+        fs::create_dir(root.join("dir")).unwrap();
+        fs::write(root.join("dir").join("file.txt"), "removed").unwrap();
+        fs::write(root.join("kept.txt"), "kept").unwrap();
+        let mut index = InodeIndex::new(&root).unwrap();
+        let removed = inode_of(&root.join("dir").join("file.txt"));
+        let kept = inode_of(&root.join("kept.txt"));
+
+        index.remove(&root.join("dir"));
+        let removed_paths = index.paths_for_inode(removed).to_vec();
+        let kept_paths = index.paths_for_inode(kept).to_vec();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(removed_paths.is_empty());
+        assert_eq!(kept_paths, vec![root.join("kept.txt")]);
+    }
+
+    #[test]
+    fn path_from_unlinked_file_is_deleted() {
+        let root = temp_dir("unlinked");
+        let path = root.join("file.txt");
+        fs::write(&path, "unlinked").unwrap();
+
+        let file = fs::File::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let resolved = path_from_file(&file);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(resolved, Err(Error::Deleted));
+    }
+
+    #[test]
+    fn path_from_file_named_like_deleted_file() {
+        let root = temp_dir("named-deleted");
+        let path = root.join("file.txt (deleted)");
+        fs::write(&path, "live").unwrap();
+
+        let file = fs::File::open(&path).unwrap();
+        let resolved = path_from_file(&file);
+        let expected = fs::canonicalize(&path).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(resolved.unwrap(), expected);
+    }
+
+    #[test]
+    fn file_handle_id_from_raw_parts() {
+        let id = FileHandleId::from_raw_parts(66306, 1, vec![1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        let rebuilt =
+            FileHandleId::from_raw_parts(id.device_id(), id.handle_type(), id.as_bytes().to_vec());
+
+        assert_eq!(rebuilt, Some(id));
+    }
+
+    #[test]
+    fn file_handle_id_rejects_oversized_handle() {
+        let handle = vec![0; libc::MAX_HANDLE_SZ as usize + 1];
+
+        assert_eq!(FileHandleId::from_raw_parts(66306, 1, handle), None);
+    }
+}
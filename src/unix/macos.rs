@@ -0,0 +1,349 @@
+use super::Error;
+use file_id::FileId;
+use std::{
+    ffi::OsStr,
+    fs, io,
+    os::unix::{ffi::OsStrExt, io::AsRawFd, io::RawFd},
+    path::{Path, PathBuf},
+};
+
+#[cfg(not(feature = "no-subprocess"))]
+use std::process::Command;
+
+/// Default volfs mount point.
+const VOL_ROOT: &str = "/.vol";
+
+/// Inode number of the root directory of APFS and HFS+ volumes.
+const ROOT_INODE: u64 = 2;
+
+/// Mount point of the APFS data volume, which is firmlinked into the root.
+const DATA_VOLUME_ROOT: &str = "/System/Volumes/Data";
+
+/// Gets the path of an open file.
+pub fn path_from_file(file: &fs::File) -> Result<PathBuf, Error> {
+    path_from_fd(file.as_raw_fd(), &mut [0; libc::PATH_MAX as usize])
+}
+
+/// Gets the mount point of the filesystem `path` is on.
+pub fn mount_root(path: &Path) -> Result<PathBuf, Error> {
+    use std::{
+        ffi::{CStr, CString},
+        mem,
+    };
+
+    let path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => path,
+        Err(err) => {
+            return Err(Error::MountRoot(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                err,
+            )))
+        }
+    };
+
+    let mut stat: libc::statfs = unsafe { mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(Error::MountRoot(io::Error::last_os_error()));
+    }
+
+    let root = unsafe { CStr::from_ptr(stat.f_mntonname.as_ptr()) };
+    Ok(PathBuf::from(OsStr::from_bytes(root.to_bytes())))
+}
+
+/// Gets the mount points listed by `getmntinfo`, with the devices they are on.
+pub fn get_mounts() -> Result<Vec<(PathBuf, u64)>, Error> {
+    use std::{ffi::CStr, ptr, slice};
+
+    let mut mounts: *mut libc::statfs = ptr::null_mut();
+    let count = unsafe { libc::getmntinfo(&mut mounts, libc::MNT_NOWAIT) };
+    if count <= 0 {
+        return Err(Error::Mounts(io::Error::last_os_error()));
+    }
+
+    // The buffer is owned by `getmntinfo` and reused by later calls.
+    let mounts = unsafe { slice::from_raw_parts(mounts, count as usize) };
+    // The first value of the filesystem id is the `st_dev` of files on it.
+    let mounts = mounts
+        .iter()
+        .map(|mount| {
+            let root = unsafe { CStr::from_ptr(mount.f_mntonname.as_ptr()) };
+            let device_id = mount.f_fsid.val[0] as u64;
+            (PathBuf::from(OsStr::from_bytes(root.to_bytes())), device_id)
+        })
+        .collect();
+
+    Ok(mounts)
+}
+
+/// Resolves the ids, sharing a single path buffer between them.
+pub fn path_from_ids(ids: &[FileId]) -> Vec<Result<PathBuf, Error>> {
+    let mut buf = vec![0; libc::PATH_MAX as usize];
+    ids.iter()
+        .map(|id| match id {
+            FileId::Inode {
+                device_id,
+                inode_number,
+            } => path_from_dev_ino(Path::new(VOL_ROOT), device_id, inode_number, &mut buf),
+            id => Err(Error::invalid_file_id(id)),
+        })
+        .collect()
+}
+
+pub fn get_path_from_id(device_id: &u64, inode_number: &u64) -> Result<PathBuf, Error> {
+    get_path_from_id_in(Path::new(VOL_ROOT), device_id, inode_number)
+}
+
+/// Resolves the inode through the volfs mounted at `vol_root`.
+pub fn get_path_from_id_in(
+    vol_root: &Path,
+    device_id: &u64,
+    inode_number: &u64,
+) -> Result<PathBuf, Error> {
+    path_from_dev_ino(
+        vol_root,
+        device_id,
+        inode_number,
+        &mut [0; libc::PATH_MAX as usize],
+    )
+}
+
+/// Gets the metadata of the inode through its volfs node,
+/// so it is the same file even if `path` has since changed.
+pub fn get_metadata(
+    device_id: &u64,
+    inode_number: &u64,
+    _path: &Path,
+) -> Result<fs::Metadata, Error> {
+    let vol_path = Path::new(VOL_ROOT)
+        .join(device_id.to_string())
+        .join(inode_number.to_string());
+
+    match fs::metadata(vol_path) {
+        Ok(metadata) => Ok(metadata),
+        Err(err) => Err(Error::Open(err)),
+    }
+}
+
+/// Resolves the volfs node of the inode, falling back to `getfileinfo`
+/// if the kernel can not provide the path of the opened node.
+///
+/// A node that can not be opened, e.g. because the inode was deleted,
+/// fails with [`Error::Open`] without falling back.
+///
+/// The root of a volume resolves to its mount point.
+/// With the `no-subprocess` feature there is no fallback,
+/// and [`Error::Unsupported`] is returned instead.
+fn path_from_dev_ino(
+    vol_root: &Path,
+    device_id: &u64,
+    inode_number: &u64,
+    buf: &mut [u8],
+) -> Result<PathBuf, Error> {
+    if let Some(mountpoint) = root_mountpoint(device_id, inode_number) {
+        return Ok(mountpoint);
+    }
+
+    let vol_device = vol_root.join(device_id.to_string());
+    let vol_path = vol_device.join(inode_number.to_string());
+    let path = match path_from_vol(&vol_path, buf) {
+        Ok(path) => path,
+        Err(Error::Open(_)) if !vol_device.exists() => {
+            return Err(Error::VolumeNotMounted {
+                device_id: *device_id,
+            })
+        }
+        #[cfg(not(feature = "no-subprocess"))]
+        Err(Error::GetPath(_)) => path_from_getfileinfo(&vol_path)?,
+        #[cfg(feature = "no-subprocess")]
+        Err(Error::GetPath(_)) => {
+            return Err(Error::Unsupported {
+                platform: "macOS with the no-subprocess feature",
+            })
+        }
+        Err(err) => return Err(err),
+    };
+
+    Ok(strip_data_volume_root(path))
+}
+
+/// Gets the mount point of the device if the inode is the root of its volume.
+///
+/// volfs resolves volume roots inconsistently, so they are matched against the mount table instead.
+/// Only [`ROOT_INODE`] is looked up, and the mount table is not refreshed if the device is missing,
+/// so resolving other inodes does not touch the table.
+fn root_mountpoint(device_id: &u64, inode_number: &u64) -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    if *inode_number != ROOT_INODE {
+        return None;
+    }
+
+    let mountpoint = super::cached_mountpoint_for_device(*device_id)?;
+    let metadata = fs::metadata(&mountpoint).ok()?;
+    (metadata.dev() == *device_id && metadata.ino() == *inode_number).then_some(mountpoint)
+}
+
+/// Rewrites a path on the APFS data volume, e.g. `/System/Volumes/Data/Users/me`,
+/// to its firmlinked path, e.g. `/Users/me`, as returned by `fs::canonicalize`.
+///
+/// The path is only rewritten if the firmlinked path is the same file.
+fn strip_data_volume_root(path: PathBuf) -> PathBuf {
+    use std::os::unix::fs::MetadataExt;
+
+    let Ok(relative) = path.strip_prefix(DATA_VOLUME_ROOT) else {
+        return path;
+    };
+
+    let firmlinked = Path::new("/").join(relative);
+    match (fs::metadata(&path), fs::metadata(&firmlinked)) {
+        (Ok(metadata), Ok(firmlinked_metadata))
+            if metadata.dev() == firmlinked_metadata.dev()
+                && metadata.ino() == firmlinked_metadata.ino() =>
+        {
+            firmlinked
+        }
+        _ => path,
+    }
+}
+
+/// Opens the volfs node and gets its path with `fcntl(F_GETPATH)`.
+fn path_from_vol(vol_path: &Path, buf: &mut [u8]) -> Result<PathBuf, Error> {
+    let file = match fs::File::open(vol_path) {
+        Ok(file) => file,
+        Err(err) => return Err(Error::Open(err)),
+    };
+
+    path_from_fd(file.as_raw_fd(), buf)
+}
+
+/// Gets the path of an open file descriptor.
+/// `buf` must be at least `PATH_MAX` bytes.
+fn path_from_fd(fd: RawFd, buf: &mut [u8]) -> Result<PathBuf, Error> {
+    debug_assert!(buf.len() >= libc::PATH_MAX as usize);
+    let ret = unsafe { libc::fcntl(fd, libc::F_GETPATH, buf.as_mut_ptr()) };
+    if ret == -1 {
+        return Err(Error::GetPath(io::Error::last_os_error()));
+    }
+
+    let len = buf.iter().position(|c| *c == 0).unwrap_or(buf.len());
+    Ok(PathBuf::from(OsStr::from_bytes(&buf[..len])))
+}
+
+/// Gets the path of the volfs node from the output of `getfileinfo`.
+#[cfg(not(feature = "no-subprocess"))]
+fn path_from_getfileinfo(vol_path: &Path) -> Result<PathBuf, Error> {
+    let output = match Command::new("getfileinfo").arg(vol_path).output() {
+        Ok(output) => output,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(Error::ToolMissing {
+                tool: "getfileinfo",
+            })
+        }
+        Err(err) => return Err(Error::Command(err)),
+    };
+
+    if !output.status.success() {
+        return Err(Error::CommandFailed {
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    let output = match String::from_utf8(output.stdout) {
+        Ok(output) => output,
+        Err(err) => return Err(Error::Decode(err)),
+    };
+
+    match parse_getfileinfo(&output) {
+        Some(path) => Ok(path),
+        None => Err(Error::NoFileInfo),
+    }
+}
+
+/// Gets the path from the `file` or `directory` line of `getfileinfo` output.
+///
+/// Lines are split on their first colon only, so the path may contain colons.
+/// The path keeps everything after the key, except for the surrounding quotes.
+/// If there is no `file` or `directory` line, e.g. because the keys are localized,
+/// the first value that is an absolute path is used instead.
+#[cfg(not(feature = "no-subprocess"))]
+fn parse_getfileinfo(output: &str) -> Option<PathBuf> {
+    let mut fallback = None;
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        let value = value.trim();
+        let value = value.strip_prefix('"').unwrap_or(value);
+        let value = value.strip_suffix('"').unwrap_or(value);
+        match key.trim() {
+            "directory" | "file" => return Some(PathBuf::from(value)),
+            _ if fallback.is_none() && value.starts_with('/') => {
+                fallback = Some(PathBuf::from(value));
+            }
+            _ => {}
+        }
+    }
+
+    fallback
+}
+
+#[cfg(all(test, not(feature = "no-subprocess")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_getfileinfo_keeps_colons_in_path() {
+        let output = "file: \"/Users/me/a:b/file\"\n\
+                      type: \"\\0\\0\\0\\0\"\n\
+                      creator: \"\\0\\0\\0\\0\"\n\
+                      attributes: avbstclinmedz\n\
+                      created: 01/02/2024 10:11:12\n\
+                      modified: 01/02/2024 10:11:12\n";
+
+        assert_eq!(
+            parse_getfileinfo(output),
+            Some(PathBuf::from("/Users/me/a:b/file"))
+        );
+    }
+
+    #[test]
+    fn parse_getfileinfo_keeps_inner_quotes() {
+        let output = "directory: \"/Users/me/\"quoted\" dir\"\n";
+
+        assert_eq!(
+            parse_getfileinfo(output),
+            Some(PathBuf::from("/Users/me/\"quoted\" dir"))
+        );
+    }
+
+    #[test]
+    fn parse_getfileinfo_falls_back_to_absolute_path_for_localized_keys() {
+        let output = "Datei: \"/Users/me/Dokumente/file\"\n\
+                      Typ: \"\\0\\0\\0\\0\"\n\
+                      erstellt: 01/02/2024 10:11:12\n";
+
+        assert_eq!(
+            parse_getfileinfo(output),
+            Some(PathBuf::from("/Users/me/Dokumente/file"))
+        );
+    }
+
+    #[test]
+    fn parse_getfileinfo_prefers_file_line_after_other_keys() {
+        let output = "alias: \"/Users/me/alias\"\n\
+                      attributes: avbstclinmedz\n\
+                      file: \"/Users/me/file\"\n";
+
+        assert_eq!(
+            parse_getfileinfo(output),
+            Some(PathBuf::from("/Users/me/file"))
+        );
+    }
+
+    #[test]
+    fn parse_getfileinfo_without_path() {
+        assert_eq!(parse_getfileinfo("attributes: avbstclinmedz\n"), None);
+    }
+}
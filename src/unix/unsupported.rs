@@ -0,0 +1,39 @@
+use super::Error;
+use file_id::FileId;
+use std::{
+    env::consts::OS,
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub fn path_from_file(_file: &fs::File) -> Result<PathBuf, Error> {
+    Err(unsupported())
+}
+
+pub fn path_from_ids(ids: &[FileId]) -> Vec<Result<PathBuf, Error>> {
+    ids.iter().map(|_| Err(unsupported())).collect()
+}
+
+pub fn get_path_from_id(_device_id: &u64, _inode_number: &u64) -> Result<PathBuf, Error> {
+    Err(unsupported())
+}
+
+pub fn get_metadata(
+    _device_id: &u64,
+    _inode_number: &u64,
+    _path: &Path,
+) -> Result<fs::Metadata, Error> {
+    Err(unsupported())
+}
+
+pub fn get_mounts() -> Result<Vec<(PathBuf, u64)>, Error> {
+    Err(unsupported())
+}
+
+pub fn mount_root(_path: &Path) -> Result<PathBuf, Error> {
+    Err(unsupported())
+}
+
+fn unsupported() -> Error {
+    Error::Unsupported { platform: OS }
+}
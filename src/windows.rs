@@ -1,186 +1,1812 @@
+use crate::{sync::Mutex, ResolveWarning};
 use file_id::FileId;
 use std::{
-    fs,
+    collections::HashMap,
+    error, fmt, fs,
     io::{self},
     mem,
-    os::windows::io::AsRawHandle,
-    path::PathBuf,
-    ptr::null,
+    os::windows::io::{AsRawHandle, OwnedHandle, RawHandle},
+    path::{Path, PathBuf},
+    ptr::{null, null_mut},
+    sync::OnceLock,
+    thread,
+    time::Duration,
 };
-use windows_sys::Win32::Foundation::HANDLE;
+use windows_sys::Win32::{
+    Foundation::HANDLE,
+    Storage::FileSystem::{FILE_ACCESS_RIGHTS, FILE_ID_DESCRIPTOR, FILE_SHARE_MODE},
+};
+
+/// Extended-length path prefix, `\\?\`.
+const EXTENDED_PREFIX: [u16; 4] = [b'\\' as u16, b'\\' as u16, b'?' as u16, b'\\' as u16];
+
+/// NT device path prefix, `\Device\`.
+const DEVICE_PREFIX: [u16; 8] = [
+    b'\\' as u16,
+    b'D' as u16,
+    b'e' as u16,
+    b'v' as u16,
+    b'i' as u16,
+    b'c' as u16,
+    b'e' as u16,
+    b'\\' as u16,
+];
+
+/// Gets the path of a file from its id, retrying up to `attempts` times
+/// while the error is [transient](Error::is_transient).
+///
+/// Waits `delay` between attempts.
+pub fn path_from_id_retry(id: &FileId, attempts: usize, delay: Duration) -> Result<PathBuf, Error> {
+    let mut attempt = 1;
+    loop {
+        match path_from_id(id) {
+            Err(err) if err.is_transient() && attempt < attempts => {
+                attempt += 1;
+                thread::sleep(delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether ids of the variant can be resolved on this platform.
+pub fn can_resolve(id: &FileId) -> bool {
+    matches!(id, FileId::LowRes { .. } | FileId::HighRes { .. })
+}
 
+/// Gets the path of a file or directory from its id.
+///
+/// Directory paths are returned without a trailing separator, except for volume roots.
+///
+/// Files on ReFS volumes should be identified by [`FileId::HighRes`] ids,
+/// since ReFS file ids do not fit in the 64-bit index of a [`FileId::LowRes`] id.
 pub fn path_from_id(id: &FileId) -> Result<PathBuf, Error> {
-    let file_handle = unsafe { file_handle_from_id(id)? };
-    unsafe { path_from_handle(&file_handle) }
+    match id {
+        FileId::HighRes {
+            volume_serial_number,
+            file_id,
+        } => path_from_volume_and_file_id(*volume_serial_number, *file_id),
+        FileId::LowRes {
+            volume_serial_number,
+            file_index,
+        } => path_from_volume_and_file_index(*volume_serial_number, *file_index),
+        id => path_from_id_with(id, &Options::default()),
+    }
 }
 
-// Gets the path to a file from its handle.
-unsafe fn path_from_handle(file: &fs::File) -> Result<PathBuf, Error> {
-    use windows_sys::core::PWSTR;
-    use windows_sys::Win32::{
-        Foundation::MAX_PATH,
-        Storage::FileSystem::{GetFinalPathNameByHandleW, FILE_NAME_NORMALIZED},
+/// Gets the path of a file from its volume serial number and 128-bit file id,
+/// as given by `GetFileInformationByHandleEx` with `FileIdInfo`.
+pub fn path_from_volume_and_file_id(
+    volume_serial_number: u64,
+    file_id: u128,
+) -> Result<PathBuf, Error> {
+    let id = FileId::HighRes {
+        volume_serial_number,
+        file_id,
+    };
+
+    path_from_id_with(&id, &Options::default())
+}
+
+/// Gets the path of a file from its volume serial number and 64-bit file index,
+/// as given by `GetFileInformationByHandle`.
+pub fn path_from_volume_and_file_index(
+    volume_serial_number: u32,
+    file_index: u64,
+) -> Result<PathBuf, Error> {
+    let id = FileId::LowRes {
+        volume_serial_number,
+        file_index,
+    };
+
+    path_from_id_with(&id, &Options::default())
+}
+
+/// Gets the path of a file from its id, checking that the path still has the id.
+///
+/// The id of the path is read without following reparse points,
+/// as the path of a symlink's id is the symlink itself.
+///
+/// # Errors
+/// + [`Error::IdReused`] if the file at the resolved path has a different id.
+pub fn path_from_id_verified(id: &FileId) -> Result<PathBuf, Error> {
+    let path = path_from_id(id)?;
+    if unsafe { id_of_path(&path, id)? } == *id {
+        Ok(path)
+    } else {
+        Err(Error::IdReused { path })
+    }
+}
+
+/// Gets the id of the file at `path`, in the same variant as `id`,
+/// without following reparse points.
+unsafe fn id_of_path(path: &Path, id: &FileId) -> Result<FileId, Error> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FileIdInfo, GetFileInformationByHandle, GetFileInformationByHandleEx,
+        BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT,
+        FILE_ID_INFO, FILE_READ_ATTRIBUTES,
+    };
+
+    let file = match fs::OpenOptions::new()
+        .access_mode(FILE_READ_ATTRIBUTES)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT)
+        .open(path)
+    {
+        Ok(file) => file,
+        Err(err) => {
+            return Err(Error::OpenFile {
+                id: *id,
+                source: err,
+            })
+        }
+    };
+
+    let handle = file.as_raw_handle() as HANDLE;
+    match id {
+        FileId::HighRes { .. } => {
+            let mut info: FILE_ID_INFO = mem::zeroed();
+            let ret = GetFileInformationByHandleEx(
+                handle,
+                FileIdInfo,
+                &mut info as *mut FILE_ID_INFO as _,
+                mem::size_of::<FILE_ID_INFO>() as u32,
+            );
+
+            if ret == 0 {
+                return Err(Error::FileInformationByHandle(io::Error::last_os_error()));
+            }
+
+            Ok(FileId::new_high_res(
+                info.VolumeSerialNumber,
+                u128::from_le_bytes(info.FileId.Identifier),
+            ))
+        }
+        FileId::LowRes { .. } => {
+            let mut info: BY_HANDLE_FILE_INFORMATION = mem::zeroed();
+            if GetFileInformationByHandle(handle, &mut info) == 0 {
+                return Err(Error::FileInformationByHandle(io::Error::last_os_error()));
+            }
+
+            Ok(FileId::new_low_res(
+                info.dwVolumeSerialNumber,
+                ((info.nFileIndexHigh as u64) << 32) | (info.nFileIndexLow as u64),
+            ))
+        }
+        FileId::Inode { .. } => Err(Error::UnsupportedIdForPlatform { id: *id }),
+    }
+}
+
+/// Gets the path of a file from its id, along with its metadata.
+///
+/// The metadata is read from the handle the path is resolved from,
+/// so both belong to the same file.
+pub fn path_and_metadata_from_id(id: &FileId) -> Result<(PathBuf, fs::Metadata), Error> {
+    let options = Options::default();
+    let file_handle = unsafe { file_handle_from_id(id, &options)? };
+    let path = unsafe { path_from_handle_with(&file_handle, &options)? };
+    match file_handle.metadata() {
+        Ok(metadata) => Ok((path, metadata)),
+        Err(err) => Err(Error::FileInformationByHandle(err)),
+    }
+}
+
+/// Gets the path of a file from its id, along with any caveats of how it was resolved.
+pub fn path_from_id_detailed(id: &FileId) -> Result<crate::Resolution, Error> {
+    let options = Options::default();
+    let mut warnings = Vec::new();
+    if matches!(id, FileId::LowRes { .. }) {
+        warnings.push(ResolveWarning::LowResSerial);
+    }
+
+    let (file_handle, volume_path_name) = unsafe { file_and_volume_from_id(id, &options)? };
+    let path = unsafe {
+        path_from_handle_detailed(&file_handle, &options, &mut Vec::new(), &mut warnings)?
+    };
+    Ok(crate::Resolution {
+        path,
+        volume_root: Some(path_from_wide(&volume_path_name)),
+        warnings,
+    })
+}
+
+/// Gets the path of a file from its id as a wide string, without a terminating null.
+///
+/// Useful for passing the path on to other Win32 functions.
+/// The conversion is lossless, even for paths that are not valid Unicode.
+pub fn path_from_id_wide(id: &FileId) -> Result<Vec<u16>, Error> {
+    use std::os::windows::ffi::OsStrExt;
+
+    let path = path_from_id(id)?;
+    Ok(path.as_os_str().encode_wide().collect())
+}
+
+/// Gets the path of a file from its id, using the given options.
+pub fn path_from_id_with(id: &FileId, options: &Options) -> Result<PathBuf, Error> {
+    let file_handle = unsafe { file_handle_from_id(id, options)? };
+    unsafe { path_from_handle_with(&file_handle, options) }
+}
+
+/// Gets the path of a file from its id on the volume at `volume_root`,
+/// without searching the mounted volumes for it.
+///
+/// Allows resolving ids on volumes that are not enumerated,
+/// e.g. a shadow copy at `\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy1\`.
+pub fn path_from_id_on_volume(volume_root: &Path, id: &FileId) -> Result<PathBuf, Error> {
+    use std::{iter, os::windows::ffi::OsStrExt};
+
+    let volume_path_name = volume_root
+        .as_os_str()
+        .encode_wide()
+        .chain(iter::once(0))
+        .collect::<Vec<_>>();
+
+    let options = Options::default();
+    let file_handle = unsafe { open_file_by_id(&volume_path_name, id, &options)? };
+    unsafe { path_from_handle_with(&file_handle, &options) }
+}
+
+/// Gets the path of a file from its id, trying the volume `hint` is on
+/// before searching the mounted volumes.
+///
+/// Useful when the id is expected to be near a known path,
+/// e.g. the directory of a watcher event.
+pub fn path_from_id_with_hint(id: &FileId, hint: &Path) -> Result<PathBuf, Error> {
+    if let Ok(root) = unsafe { volume_root(hint) } {
+        if let Ok(path) = path_from_id_on_volume(&root, id) {
+            return Ok(path);
+        }
+    }
+
+    path_from_id(id)
+}
+
+/// Gets the paths of many ids at once.
+///
+/// Results are in the same order as `ids`.
+/// Ids are grouped by volume, so each volume is found and opened only once.
+/// The path buffer is also shared between ids.
+pub fn path_from_ids(ids: &[FileId]) -> Vec<Result<PathBuf, Error>> {
+    let options = Options::default();
+    let mut buf = Vec::new();
+    let mut paths = ids.iter().map(|_| None).collect::<Vec<_>>();
+    let mut groups = HashMap::<SerialNumber, Vec<usize>>::new();
+    for (index, id) in ids.iter().enumerate() {
+        match SerialNumber::from_id(id) {
+            Ok(serial_number) => groups.entry(serial_number).or_default().push(index),
+            Err(err) => paths[index] = Some(Err(err)),
+        }
+    }
+
+    for (serial_number, indices) in groups {
+        let volume_handle = match unsafe { volume_handle_from_serial_number(serial_number) } {
+            Ok(volume_handle) => volume_handle,
+            Err(err) => {
+                for index in indices {
+                    paths[index] = Some(Err(clone_volume_error(&err, serial_number)));
+                }
+
+                continue;
+            }
+        };
+
+        for index in indices {
+            let path = unsafe {
+                open_file_by_id_on(&volume_handle, &ids[index], &options).and_then(|file| {
+                    path_from_handle_detailed(&file, &options, &mut buf, &mut Vec::new())
+                })
+            };
+
+            paths[index] = Some(path);
+        }
+    }
+
+    paths
+        .into_iter()
+        .map(|path| path.expect("every id is resolved"))
+        .collect()
+}
+
+/// Copies an error from finding a volume, for each id on the volume.
+fn clone_volume_error(err: &Error, serial_number: SerialNumber) -> Error {
+    let copy = |err: &io::Error| match err.raw_os_error() {
+        Some(code) => io::Error::from_raw_os_error(code),
+        None => io::Error::new(err.kind(), err.to_string()),
+    };
+
+    match err {
+        Error::VolumeHandle {
+            serial_number,
+            source,
+        } => Error::VolumeHandle {
+            serial_number: *serial_number,
+            source: copy(source),
+        },
+        Error::FindVolume {
+            serial_number,
+            source,
+        } => Error::FindVolume {
+            serial_number: *serial_number,
+            source: copy(source),
+        },
+        Error::VolumePathNames(err) => Error::VolumePathNames(copy(err)),
+        _ => Error::VolumeNotMounted {
+            serial_number: serial_number.value(),
+        },
+    }
+}
+
+/// Opens a file from its id.
+///
+/// The file is opened for reading and can be passed to [`path_from_file`].
+pub fn file_from_id(id: &FileId) -> Result<fs::File, Error> {
+    use windows_sys::Win32::Storage::FileSystem::FILE_GENERIC_READ;
+
+    let options = Options {
+        access: FILE_GENERIC_READ,
+        ..Default::default()
+    };
+
+    unsafe { file_handle_from_id(id, &options) }
+}
+
+/// Gets the root of the volume a file is on, and the path of the file relative to it.
+pub fn path_from_id_relative(id: &FileId) -> Result<(PathBuf, PathBuf), Error> {
+    let path = path_from_id(id)?;
+    let root = unsafe { volume_root(&path)? };
+    let relative = match path.strip_prefix(&root) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(err) => {
+            return Err(Error::VolumeRoot(io::Error::new(
+                io::ErrorKind::NotFound,
+                err,
+            )))
+        }
+    };
+
+    Ok((root, relative))
+}
+
+/// Gets the path of an open file.
+pub fn path_from_file(file: &fs::File) -> Result<PathBuf, Error> {
+    path_from_file_with(file, &Options::default())
+}
+
+/// Gets the path of an open file, using the given options.
+///
+/// A file that is pending deletion may still have a path, which is returned.
+/// If it no longer has one, [`Error::Deleted`] is returned.
+pub fn path_from_file_with(file: &fs::File, options: &Options) -> Result<PathBuf, Error> {
+    match unsafe { path_from_handle_with(file, options) } {
+        Err(Error::FinalPathName(_)) if unsafe { is_delete_pending(file) } => Err(Error::Deleted),
+        result => result,
+    }
+}
+
+/// Whether the file will be deleted once its handles are closed.
+unsafe fn is_delete_pending(file: &fs::File) -> bool {
+    use windows_sys::Win32::Storage::FileSystem::{
+        FileStandardInfo, GetFileInformationByHandleEx, FILE_STANDARD_INFO,
     };
 
-    let path = [0; MAX_PATH as usize];
-    let size = GetFinalPathNameByHandleW(
+    let mut info: FILE_STANDARD_INFO = mem::zeroed();
+    let ret = GetFileInformationByHandleEx(
         file.as_raw_handle() as HANDLE,
-        path.as_ptr() as PWSTR,
-        MAX_PATH,
-        FILE_NAME_NORMALIZED,
+        FileStandardInfo,
+        &mut info as *mut FILE_STANDARD_INFO as _,
+        mem::size_of::<FILE_STANDARD_INFO>() as u32,
     );
 
-    if size == 0 {
-        Err(Error::FinalPathName(io::Error::last_os_error()))
-    } else if size > MAX_PATH {
-        Err(Error::FinalPathName(io::Error::new(
-            io::ErrorKind::OutOfMemory,
-            format!("path buffer requires {size} bytes but only {MAX_PATH} were allocated"),
-        )))
-    } else {
-        let path = path.into_iter().take(size as usize).collect::<Vec<_>>();
-        let Ok(path) = String::from_utf16(path.as_slice()) else {
-            return Err(Error::FinalPathName(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "could not decode path",
-            )));
+    ret != 0 && info.DeletePending != 0
+}
+
+/// Number of directories whose case sensitivity is remembered before the cache is cleared.
+const CASE_SENSITIVE_DIRS_CAPACITY: usize = 1024;
+
+/// Process-wide cache of whether directories are case-sensitive.
+fn case_sensitive_dirs() -> &'static Mutex<HashMap<PathBuf, bool>> {
+    static DIRS: OnceLock<Mutex<HashMap<PathBuf, bool>>> = OnceLock::new();
+    DIRS.get_or_init(Default::default)
+}
+
+/// Whether the directory at `path` is case-sensitive, opening each directory only once.
+///
+/// The flag can only be changed while a directory is empty, so it is remembered per path.
+/// A directory replaced by a new one with a different flag may be reported stale
+/// until the cache fills up and is cleared.
+unsafe fn is_case_sensitive_dir_cached(path: &Path) -> bool {
+    if let Some(case_sensitive) = case_sensitive_dirs().lock().get(path) {
+        return *case_sensitive;
+    }
+
+    let case_sensitive = is_case_sensitive_dir(path);
+    let mut dirs = case_sensitive_dirs().lock();
+    if dirs.len() >= CASE_SENSITIVE_DIRS_CAPACITY {
+        dirs.clear();
+    }
+
+    dirs.insert(path.to_path_buf(), case_sensitive);
+    case_sensitive
+}
+
+/// Whether the directory at `path` has the per-directory case sensitivity flag set.
+///
+/// Directories whose flag can not be read are treated as case-insensitive.
+unsafe fn is_case_sensitive_dir(path: &Path) -> bool {
+    use std::os::windows::fs::OpenOptionsExt;
+    use windows_sys::Win32::{
+        Storage::FileSystem::{
+            FileCaseSensitiveInfo, GetFileInformationByHandleEx, FILE_FLAG_BACKUP_SEMANTICS,
+            FILE_READ_ATTRIBUTES,
+        },
+        System::WindowsProgramming::FILE_CASE_SENSITIVE_INFO,
+    };
+
+    /// `FILE_CS_FLAG_CASE_SENSITIVE_DIR`, missing from `windows-sys`.
+    const CASE_SENSITIVE_DIR: u32 = 0x1;
+
+    let Ok(dir) = fs::OpenOptions::new()
+        .access_mode(FILE_READ_ATTRIBUTES)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+    else {
+        return false;
+    };
+
+    let mut info: FILE_CASE_SENSITIVE_INFO = mem::zeroed();
+    let ret = GetFileInformationByHandleEx(
+        dir.as_raw_handle() as HANDLE,
+        FileCaseSensitiveInfo,
+        &mut info as *mut FILE_CASE_SENSITIVE_INFO as _,
+        mem::size_of::<FILE_CASE_SENSITIVE_INFO>() as u32,
+    );
+
+    ret != 0 && info.Flags & CASE_SENSITIVE_DIR != 0
+}
+
+/// Gets every path a file is reachable under, one for each of its hard links.
+///
+/// All paths are absolute and use the same form as [`path_from_id`].
+pub fn all_paths_from_id(id: &FileId) -> Result<Vec<PathBuf>, Error> {
+    let options = Options::default();
+    let file_handle = unsafe { file_handle_from_id(id, &options)? };
+    let path = unsafe { path_from_handle(&file_handle, NameMode::Normalized, PathStyle::Dos)? };
+    let resolved = unsafe { path_from_handle_with(&file_handle, &options)? };
+    let paths = unsafe { link_paths(path.clone())? }
+        .into_iter()
+        .map(|link| {
+            if link == path {
+                resolved.clone()
+            } else {
+                format_path(link, &options)
+            }
+        })
+        .collect();
+
+    Ok(paths)
+}
+
+/// A local volume, as seen when resolving ids.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VolumeInfo {
+    /// Volume GUID path, e.g. `\\?\Volume{GUID}\`.
+    pub guid_path: PathBuf,
+
+    /// Drive letters and mount folders of the volume.
+    pub mount_paths: Vec<PathBuf>,
+
+    pub serial_number: u64,
+}
+
+/// Lists the local volumes ids are resolved against.
+///
+/// Useful for debugging ids that do not resolve.
+/// Volumes that can not currently be read, e.g. empty card readers, are skipped.
+pub fn enumerate_volumes() -> Result<Vec<VolumeInfo>, Error> {
+    let mut volumes = Vec::new();
+    unsafe {
+        visit_volume_names(&mut |volume_name| {
+            let mount_paths = get_volume_path_names(volume_name)?
+                .iter()
+                .map(|path_name| path_from_wide(path_name))
+                .collect();
+
+            let serial_number = match get_volume_serial_number_from_path(volume_name) {
+                Ok(serial_number) => serial_number,
+                Err(err) if is_volume_unavailable(&err) => return Ok(false),
+                Err(err) => return Err(err),
+            };
+
+            volumes.push(VolumeInfo {
+                guid_path: path_from_wide(volume_name),
+                mount_paths,
+                serial_number,
+            });
+
+            Ok(false)
+        })?;
+    }
+
+    Ok(volumes)
+}
+
+/// Converts a wide string, up to its first null, to a path.
+fn path_from_wide(wide: &[u16]) -> PathBuf {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+    let len = wide.iter().position(|c| *c == 0).unwrap_or(wide.len());
+    PathBuf::from(OsString::from_wide(&wide[..len]))
+}
+
+/// Resolves ids using a cache of volume serial numbers to volume paths.
+///
+/// Finding the volume of an id requires enumerating every volume on the system,
+/// so reusing a resolver avoids repeating the enumeration for each id.
+#[derive(Debug, Default)]
+pub struct VolumeResolver {
+    volumes: HashMap<u64, Vec<u16>>,
+}
+
+impl VolumeResolver {
+    /// Creates a resolver for the currently mounted volumes.
+    pub fn new() -> Result<Self, Error> {
+        let mut resolver = Self::default();
+        resolver.refresh()?;
+        Ok(resolver)
+    }
+
+    /// Rebuilds the volume cache.
+    /// Should be called when a volume is mounted or unmounted.
+    pub fn refresh(&mut self) -> Result<(), Error> {
+        self.volumes = unsafe { get_volume_path_names_by_serial_number()? };
+        Ok(())
+    }
+
+    pub fn path_from_id(&self, id: &FileId) -> Result<PathBuf, Error> {
+        self.path_from_id_with(id, &Options::default())
+    }
+
+    pub fn path_from_id_with(&self, id: &FileId, options: &Options) -> Result<PathBuf, Error> {
+        let serial_number = SerialNumber::from_id(id)?;
+        let Some(volume_path_name) = self.volume_path_name(serial_number) else {
+            return Err(Error::VolumeNotMounted {
+                serial_number: serial_number.value(),
+            });
+        };
+
+        let file_handle = unsafe { open_file_by_id(volume_path_name, id, options)? };
+        unsafe { path_from_handle_with(&file_handle, options) }
+    }
+
+    fn volume_path_name(&self, serial_number: SerialNumber) -> Option<&[u16]> {
+        let volume_path_name = match serial_number {
+            SerialNumber::High(serial_number) => self.volumes.get(&serial_number),
+            SerialNumber::Low(_) => self
+                .volumes
+                .iter()
+                .find(|(volume_sn, _)| serial_number.matches(**volume_sn))
+                .map(|(_, path_name)| path_name),
         };
 
-        Ok(PathBuf::from(PathBuf::from(path)))
+        volume_path_name.map(Vec::as_slice)
     }
 }
 
-/// Gets a file handle from an id.
-unsafe fn file_handle_from_id(file_id: &FileId) -> Result<fs::File, Error> {
-    use std::{os::raw::c_void, os::windows::prelude::*};
+/// Options for resolving a path.
+#[derive(Clone, Copy, Debug)]
+pub struct Options {
+    /// Convert `\\?\C:\foo` to `C:\foo` and `\\?\UNC\server\share` to `\\server\share`.
+    pub strip_extended_prefix: bool,
+
+    /// Which name of the file to return.
+    pub name: NameMode,
+
+    /// Open reparse points (symlinks, junctions) themselves instead of their targets.
+    pub open_reparse_point: bool,
+
+    /// How the volume of the path is named.
+    pub path_style: PathStyle,
+
+    /// Sharing mode the file is opened with.
+    ///
+    /// Defaults to `FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE`,
+    /// so files opened by other processes do not cause sharing violations.
+    pub share_mode: FILE_SHARE_MODE,
+
+    /// Access the file is opened with.
+    ///
+    /// Defaults to `FILE_READ_ATTRIBUTES`, which is enough to get the path
+    /// of files the caller is not allowed to read.
+    pub access: FILE_ACCESS_RIGHTS,
+
+    /// Uppercase the drive letter, e.g. `c:\foo` becomes `C:\foo`,
+    /// to match `fs::canonicalize`. Disabled by default,
+    /// so paths keep the drive letter the system returns.
+    pub uppercase_drive_letter: bool,
+
+    /// Follow mount points (junctions) to their targets. Enabled by default.
+    ///
+    /// If disabled, mount points are opened themselves,
+    /// while other reparse points, such as symlinks, are still followed
+    /// unless [`open_reparse_point`](Self::open_reparse_point) is set.
+    pub traverse_mount_points: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        use windows_sys::Win32::Storage::FileSystem::{
+            FILE_READ_ATTRIBUTES, FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE,
+        };
+
+        Self {
+            strip_extended_prefix: false,
+            name: NameMode::default(),
+            open_reparse_point: false,
+            path_style: PathStyle::default(),
+            share_mode: FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            access: FILE_READ_ATTRIBUTES,
+            uppercase_drive_letter: false,
+            traverse_mount_points: true,
+        }
+    }
+}
+
+/// How the volume of a returned path is named.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PathStyle {
+    /// The drive letter or UNC share, e.g. `\\?\C:\dir\file`.
+    #[default]
+    Dos,
+
+    /// The volume GUID path, e.g. `\\?\Volume{GUID}\dir\file`.
+    /// Stays the same when the volume's drive letter changes.
+    VolumeGuid,
+}
+
+/// Name of a file returned from its handle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NameMode {
+    /// The normalized name, with the canonical casing of each component.
+    ///
+    /// In case-sensitive directories, e.g. those shared with WSL,
+    /// the normalized casing may not match the file,
+    /// so the opened name is used for files in them instead.
+    #[default]
+    Normalized,
+
+    /// The name the file was opened with.
+    Opened,
+}
+
+/// Removes the extended-length prefix from a path.
+///
+/// + `\\?\C:\foo` becomes `C:\foo`.
+/// + `\\?\UNC\server\share` becomes `\\server\share`.
+/// + Paths without a prefix, or which can not be expressed without one
+///   (e.g. `\\?\Volume{GUID}\`), are returned unchanged.
+pub fn strip_extended_prefix(path: PathBuf) -> PathBuf {
+    use std::{
+        ffi::OsString,
+        os::windows::ffi::{OsStrExt, OsStringExt},
+    };
+
+    const UNC: [u16; 4] = [b'U' as u16, b'N' as u16, b'C' as u16, b'\\' as u16];
+
+    let wide = path.as_os_str().encode_wide().collect::<Vec<_>>();
+    let Some(rest) = wide.strip_prefix(&EXTENDED_PREFIX[..]) else {
+        return path;
+    };
+
+    if let Some(share) = rest.strip_prefix(&UNC[..]) {
+        let mut unc = vec![b'\\' as u16, b'\\' as u16];
+        unc.extend_from_slice(share);
+        return PathBuf::from(OsString::from_wide(&unc));
+    }
+
+    match rest {
+        [drive, colon, ..]
+            if *colon == b':' as u16
+                && char::from_u32(*drive as u32).is_some_and(|c| c.is_ascii_alphabetic()) =>
+        {
+            PathBuf::from(OsString::from_wide(rest))
+        }
+
+        _ => path,
+    }
+}
+
+/// Removes a trailing separator so files and directories are returned in the same form.
+/// Volume roots (e.g. `\\?\C:\`) keep theirs.
+fn trim_trailing_separator(path: PathBuf) -> PathBuf {
+    if path.parent().is_some() && path.as_os_str().to_string_lossy().ends_with('\\') {
+        path.components().collect()
+    } else {
+        path
+    }
+}
+
+/// Gets the path of an open file, using the given options and `buf` to hold the path.
+///
+/// Returns the same path as [`path_from_file_with`].
+/// The buffer is grown as needed, so reusing it between calls avoids allocating a buffer each time.
+pub fn path_from_handle_into(
+    file: &fs::File,
+    options: &Options,
+    buf: &mut Vec<u16>,
+) -> Result<PathBuf, Error> {
+    match unsafe { path_from_handle_detailed(file, options, buf, &mut Vec::new()) } {
+        Err(Error::FinalPathName(_)) if unsafe { is_delete_pending(file) } => Err(Error::Deleted),
+        result => result,
+    }
+}
+
+/// Gets the path to a file from its handle, using the given options.
+unsafe fn path_from_handle_with(file: &fs::File, options: &Options) -> Result<PathBuf, Error> {
+    path_from_handle_detailed(file, options, &mut Vec::new(), &mut Vec::new())
+}
+
+/// Gets the path to a file from its handle, using `buf` to hold the path
+/// and adding any caveats of the resolution to `warnings`.
+unsafe fn path_from_handle_detailed(
+    file: &fs::File,
+    options: &Options,
+    buf: &mut Vec<u16>,
+    warnings: &mut Vec<ResolveWarning>,
+) -> Result<PathBuf, Error> {
+    let mut path = path_from_handle_buf(file, options.name, options.path_style, buf)?;
+    if options.name == NameMode::Normalized
+        && path
+            .parent()
+            .is_some_and(|parent| is_case_sensitive_dir_cached(parent))
+    {
+        path = path_from_handle_buf(file, NameMode::Opened, options.path_style, buf)?;
+        warnings.push(ResolveWarning::OpenedName);
+    }
+
+    if options.path_style == PathStyle::Dos && is_volume_guid_path(&path) {
+        warnings.push(ResolveWarning::VolumeGuidPath);
+    }
+
+    Ok(format_path(path, options))
+}
+
+/// Applies the formatting options to a resolved path.
+fn format_path(mut path: PathBuf, options: &Options) -> PathBuf {
+    if options.strip_extended_prefix {
+        path = strip_extended_prefix(path);
+    }
+
+    if options.uppercase_drive_letter {
+        path = uppercase_drive_letter(path);
+    }
+
+    path
+}
+
+/// Whether the path starts with a volume GUID path, e.g. `\\?\Volume{GUID}\`.
+fn is_volume_guid_path(path: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+
+    const PREFIX: &str = r"\\?\Volume{";
+    path.as_os_str()
+        .encode_wide()
+        .take(PREFIX.len())
+        .eq(PREFIX.encode_utf16())
+}
+
+/// Uppercases the drive letter of a path, with or without the extended-length prefix.
+fn uppercase_drive_letter(path: PathBuf) -> PathBuf {
+    use std::{
+        ffi::OsString,
+        os::windows::ffi::{OsStrExt, OsStringExt},
+    };
+
+    let mut wide = path.as_os_str().encode_wide().collect::<Vec<_>>();
+    let drive = if wide.starts_with(&EXTENDED_PREFIX) {
+        EXTENDED_PREFIX.len()
+    } else {
+        0
+    };
+
+    match wide.get(drive..drive + 2) {
+        Some([letter, colon]) if *colon == b':' as u16 && *letter < 0x80 => {
+            let letter = *letter as u8;
+            if !letter.is_ascii_lowercase() {
+                return path;
+            }
+
+            wide[drive] = letter.to_ascii_uppercase() as u16;
+            PathBuf::from(OsString::from_wide(&wide))
+        }
+        _ => path,
+    }
+}
+
+// Gets the path to a file from its handle.
+unsafe fn path_from_handle(
+    file: &fs::File,
+    name: NameMode,
+    path_style: PathStyle,
+) -> Result<PathBuf, Error> {
+    path_from_handle_buf(file, name, path_style, &mut Vec::new())
+}
+
+/// Gets the path to a file from its handle, using `path` as the buffer.
+unsafe fn path_from_handle_buf(
+    file: &fs::File,
+    name: NameMode,
+    path_style: PathStyle,
+    path: &mut Vec<u16>,
+) -> Result<PathBuf, Error> {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt};
     use windows_sys::Win32::{
-        Foundation::INVALID_HANDLE_VALUE,
-        Security::SECURITY_ATTRIBUTES,
+        Foundation::{GetLastError, ERROR_PATH_NOT_FOUND, MAX_PATH},
         Storage::FileSystem::{
-            ExtendedFileIdType, OpenFileById, FILE_FLAG_BACKUP_SEMANTICS, FILE_GENERIC_READ,
-            FILE_ID_128, FILE_ID_DESCRIPTOR, FILE_ID_DESCRIPTOR_0, FILE_SHARE_READ,
+            GetFinalPathNameByHandleW, FILE_NAME_NORMALIZED, FILE_NAME_OPENED, VOLUME_NAME_DOS,
+            VOLUME_NAME_GUID,
+        },
+    };
+
+    let name_flag = match name {
+        NameMode::Normalized => FILE_NAME_NORMALIZED,
+        NameMode::Opened => FILE_NAME_OPENED,
+    };
+
+    let mut volume_flag = match path_style {
+        PathStyle::Dos => VOLUME_NAME_DOS,
+        PathStyle::VolumeGuid => VOLUME_NAME_GUID,
+    };
+
+    // On success the returned size is the length of the path without the terminating null,
+    // so it is always less than the buffer length.
+    // If the buffer is too small, it is the required length including the null.
+    if path.len() < MAX_PATH as usize {
+        path.resize(MAX_PATH as usize, 0);
+    }
+
+    let size = loop {
+        let size = GetFinalPathNameByHandleW(
+            file.as_raw_handle() as HANDLE,
+            path.as_mut_ptr(),
+            path.len() as u32,
+            name_flag | volume_flag,
+        );
+
+        if size == 0 {
+            // Volumes without a drive letter or mount folder have no DOS path,
+            // so their volume GUID path is returned instead.
+            if volume_flag == VOLUME_NAME_DOS && GetLastError() == ERROR_PATH_NOT_FOUND {
+                volume_flag = VOLUME_NAME_GUID;
+                continue;
+            }
+
+            return Err(Error::FinalPathName(io::Error::last_os_error()));
+        }
+
+        let size = size as usize;
+        if size < path.len() {
+            break size;
+        }
+
+        path.resize(size, 0);
+    };
+
+    let path = if path[..size].starts_with(&DEVICE_PREFIX) {
+        dos_path_from_device_path(&path[..size])?
+    } else {
+        PathBuf::from(OsString::from_wide(&path[..size]))
+    };
+
+    Ok(trim_trailing_separator(path))
+}
+
+/// Translates an NT device path, e.g. `\Device\HarddiskVolume3\foo`,
+/// to a DOS path by finding the drive the device is mapped to.
+unsafe fn dos_path_from_device_path(device_path: &[u16]) -> Result<PathBuf, Error> {
+    let drives = get_logical_drives().unwrap_or_default();
+    map_device_path(device_path, drives, |drive| query_dos_device(drive))
+}
+
+/// Maps an NT device path to the first of `drives` whose device, as given by `query`,
+/// is a prefix of it.
+///
+/// `query` is given the drive without its trailing separator, e.g. `C:`,
+/// and returns the device it is currently mapped to, e.g. `\Device\HarddiskVolume3`.
+fn map_device_path(
+    device_path: &[u16],
+    drives: Vec<Vec<u16>>,
+    mut query: impl FnMut(&[u16]) -> Option<Vec<u16>>,
+) -> Result<PathBuf, Error> {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+    for mut drive in drives {
+        if drive.last() == Some(&(b'\\' as u16)) {
+            drive.pop();
+        }
+
+        let Some(device) = query(&drive) else {
+            continue;
+        };
+
+        if device.is_empty() || !device_path.starts_with(&device) {
+            continue;
+        }
+
+        let rest = &device_path[device.len()..];
+        if !rest.is_empty() && rest[0] != b'\\' as u16 {
+            continue;
+        }
+
+        drive.extend_from_slice(rest);
+        return Ok(PathBuf::from(OsString::from_wide(&drive)));
+    }
+
+    Err(Error::DevicePathUnresolved {
+        device_path: PathBuf::from(OsString::from_wide(device_path)),
+    })
+}
+
+/// Gets the device a drive, e.g. `C:`, is currently mapped to.
+unsafe fn query_dos_device(drive: &[u16]) -> Option<Vec<u16>> {
+    use windows_sys::Win32::{Foundation::MAX_PATH, Storage::FileSystem::QueryDosDeviceW};
+
+    let device_name = drive.iter().copied().chain([0]).collect::<Vec<_>>();
+    let mut target = vec![0; MAX_PATH as usize];
+    let size = QueryDosDeviceW(
+        device_name.as_ptr(),
+        target.as_mut_ptr(),
+        target.len() as u32,
+    );
+    if size == 0 {
+        return None;
+    }
+
+    // The target is a list of null-terminated strings, the first being the current mapping.
+    target[..size as usize]
+        .split(|c| *c == 0)
+        .next()
+        .map(<[u16]>::to_vec)
+}
+
+/// Gets the root of the volume `path` is on.
+unsafe fn volume_root(path: &Path) -> Result<PathBuf, Error> {
+    use std::{
+        ffi::OsString,
+        iter,
+        os::windows::ffi::{OsStrExt, OsStringExt},
+    };
+    use windows_sys::Win32::{Foundation::MAX_PATH, Storage::FileSystem::GetVolumePathNameW};
+
+    let path_name = path
+        .as_os_str()
+        .encode_wide()
+        .chain(iter::once(0))
+        .collect::<Vec<_>>();
+
+    let mut root = vec![0; path_name.len().max(MAX_PATH as usize)];
+    if GetVolumePathNameW(path_name.as_ptr(), root.as_mut_ptr(), root.len() as u32) == 0 {
+        return Err(Error::VolumeRoot(io::Error::last_os_error()));
+    }
+
+    let len = root.iter().position(|c| *c == 0).unwrap_or(root.len());
+    Ok(PathBuf::from(OsString::from_wide(&root[..len])))
+}
+
+/// Gets the paths of all hard links to the file at `path`.
+///
+/// Link names are relative to the volume root, so the root is taken from `path`
+/// by removing the link name it ends with.
+unsafe fn link_paths(path: PathBuf) -> Result<Vec<PathBuf>, Error> {
+    use std::{
+        ffi::OsString,
+        os::windows::ffi::{OsStrExt, OsStringExt},
+    };
+    use windows_sys::Win32::{
+        Foundation::{
+            GetLastError, ERROR_HANDLE_EOF, ERROR_MORE_DATA, INVALID_HANDLE_VALUE, MAX_PATH,
         },
+        Storage::FileSystem::{FindClose, FindFirstFileNameW, FindNextFileNameW},
+    };
+
+    let wide_path = path.as_os_str().encode_wide().collect::<Vec<_>>();
+    let mut path_name = wide_path.clone();
+    path_name.push(0);
+
+    let mut link = vec![0; MAX_PATH as usize];
+    let mut size = link.len() as u32;
+    let mut find_handle = FindFirstFileNameW(path_name.as_ptr(), 0, &mut size, link.as_mut_ptr());
+    if find_handle == INVALID_HANDLE_VALUE && GetLastError() == ERROR_MORE_DATA {
+        link.resize(size as usize, 0);
+        size = link.len() as u32;
+        find_handle = FindFirstFileNameW(path_name.as_ptr(), 0, &mut size, link.as_mut_ptr());
+    }
+
+    if find_handle == INVALID_HANDLE_VALUE {
+        return Err(Error::FileNames(io::Error::last_os_error()));
+    }
+
+    let mut links = Vec::new();
+    'links: loop {
+        let len = link.iter().position(|c| *c == 0).unwrap_or(link.len());
+        links.push(link[..len].to_vec());
+
+        loop {
+            size = link.len() as u32;
+            if FindNextFileNameW(find_handle, &mut size, link.as_mut_ptr()) != 0 {
+                break;
+            }
+
+            match GetLastError() {
+                ERROR_MORE_DATA => link.resize(size as usize, 0),
+                ERROR_HANDLE_EOF => break 'links,
+                _ => {
+                    let err = io::Error::last_os_error();
+                    FindClose(find_handle);
+                    return Err(Error::FileNames(err));
+                }
+            }
+        }
+    }
+
+    FindClose(find_handle);
+
+    let Some(root) = links
+        .iter()
+        .find(|link| wide_path.ends_with(link))
+        .map(|link| &wide_path[..wide_path.len() - link.len()])
+    else {
+        return Err(Error::FileNames(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no hard link matches the final path name",
+        )));
+    };
+
+    let paths = links
+        .into_iter()
+        .map(|link| {
+            let mut path = root.to_vec();
+            path.extend(link);
+            PathBuf::from(OsString::from_wide(&path))
+        })
+        .collect();
+
+    Ok(paths)
+}
+
+/// Gets a file handle from an id.
+///
+/// Volume paths are cached in a process-wide [`VolumeResolver`],
+/// which is updated when the volume of an id is not in the cache,
+/// or the cached volume can not be opened or is no longer the volume of the id.
+unsafe fn file_handle_from_id(file_id: &FileId, options: &Options) -> Result<fs::File, Error> {
+    file_and_volume_from_id(file_id, options).map(|(file, _)| file)
+}
+
+/// Gets a file handle from an id, along with the path of the volume it was opened through.
+unsafe fn file_and_volume_from_id(
+    file_id: &FileId,
+    options: &Options,
+) -> Result<(fs::File, Vec<u16>), Error> {
+    let serial_number = SerialNumber::from_id(file_id)?;
+    if let Some(volume_path_name) = cached_volume_path_name(serial_number) {
+        match open_file_by_id(&volume_path_name, file_id, options) {
+            Ok(file) => return Ok((file, volume_path_name)),
+            // The cached volume may have been replaced,
+            // e.g. by another volume mounted at its drive letter.
+            Err(Error::VolumeHandle { .. } | Error::VolumeMismatch { .. }) => {
+                invalidate_volume_path_name(serial_number)
+            }
+            // Otherwise the file itself could not be opened, e.g. because it was deleted.
+            Err(err) => return Err(err),
+        }
+    }
+
+    let volume_path_name = scan_volume_path_name(serial_number)?;
+    let file = open_file_by_id(&volume_path_name, file_id, options)?;
+    Ok((file, volume_path_name))
+}
+
+/// Rebuilds the process-wide cache of volume paths used by [`path_from_id`] and friends.
+///
+/// The cache is refreshed for an id whenever its cached volume can not be opened
+/// or has a different serial number, so this is only needed to pick up volume changes eagerly.
+pub fn refresh_volume_cache() -> Result<(), Error> {
+    let volumes = unsafe { get_volume_path_names_by_serial_number()? };
+    volume_cache().lock().volumes = volumes;
+    Ok(())
+}
+
+/// Process-wide cache of volume paths.
+fn volume_cache() -> &'static Mutex<VolumeResolver> {
+    static RESOLVER: OnceLock<Mutex<VolumeResolver>> = OnceLock::new();
+    RESOLVER.get_or_init(Default::default)
+}
+
+/// Gets the path of the volume from the process-wide cache.
+fn cached_volume_path_name(serial_number: SerialNumber) -> Option<Vec<u16>> {
+    volume_cache()
+        .lock()
+        .volume_path_name(serial_number)
+        .map(<[u16]>::to_vec)
+}
+
+/// Removes the volume from the process-wide cache.
+fn invalidate_volume_path_name(serial_number: SerialNumber) {
+    volume_cache()
+        .lock()
+        .volumes
+        .retain(|volume_sn, _| !serial_number.matches(*volume_sn));
+}
+
+/// Searches the mounted volumes for the volume, adding it to the process-wide cache.
+unsafe fn scan_volume_path_name(serial_number: SerialNumber) -> Result<Vec<u16>, Error> {
+    let (volume_serial_number, volume_path_name) =
+        get_volume_path_name_from_serial_number(serial_number)?;
+
+    volume_cache()
+        .lock()
+        .volumes
+        .insert(volume_serial_number, volume_path_name.clone());
+
+    Ok(volume_path_name)
+}
+
+/// Opens a handle to the volume, from the process-wide cache if possible.
+unsafe fn volume_handle_from_serial_number(
+    serial_number: SerialNumber,
+) -> Result<OwnedHandle, Error> {
+    if let Some(volume_path_name) = cached_volume_path_name(serial_number) {
+        // The drive letter may now belong to a different volume, so check its serial.
+        if let Ok(volume_handle) = get_volume_handle_from_path(&volume_path_name) {
+            let volume_sn =
+                get_volume_serial_number_from_handle(volume_handle.as_raw_handle() as HANDLE);
+            if volume_sn.is_ok_and(|volume_sn| serial_number.matches(volume_sn)) {
+                return Ok(volume_handle);
+            }
+        }
+
+        invalidate_volume_path_name(serial_number);
+    }
+
+    let volume_path_name = scan_volume_path_name(serial_number)?;
+    match get_volume_handle_from_path(&volume_path_name) {
+        Ok(volume_handle) => Ok(volume_handle),
+        Err(err) => Err(Error::VolumeHandle {
+            serial_number: Some(serial_number.value()),
+            source: err,
+        }),
+    }
+}
+
+/// Gets the descriptor used to open a file by its id.
+///
+/// High resolution ids use the 128-bit `ExtendedFileIdType`, which both NTFS and ReFS accept.
+fn file_id_descriptor(file_id: &FileId) -> Result<FILE_ID_DESCRIPTOR, Error> {
+    use windows_sys::Win32::Storage::FileSystem::{
+        ExtendedFileIdType, FileIdType, FILE_ID_128, FILE_ID_DESCRIPTOR_0,
     };
 
     match file_id {
         FileId::HighRes {
-            volume_serial_number,
+            volume_serial_number: _,
             file_id,
-        } => {
-            let volume_path_name =
-                get_volume_path_name_from_serial_number(volume_serial_number.clone())?;
-
-            let file_id_descriptor = FILE_ID_DESCRIPTOR {
-                dwSize: mem::size_of::<FILE_ID_DESCRIPTOR>() as u32,
-                Type: ExtendedFileIdType,
-                Anonymous: FILE_ID_DESCRIPTOR_0 {
-                    ExtendedFileId: FILE_ID_128 {
-                        Identifier: file_id.to_ne_bytes(),
-                    },
+        } => Ok(FILE_ID_DESCRIPTOR {
+            dwSize: mem::size_of::<FILE_ID_DESCRIPTOR>() as u32,
+            Type: ExtendedFileIdType,
+            Anonymous: FILE_ID_DESCRIPTOR_0 {
+                ExtendedFileId: FILE_ID_128 {
+                    Identifier: file_id.to_ne_bytes(),
                 },
-            };
+            },
+        }),
+
+        FileId::LowRes {
+            volume_serial_number: _,
+            file_index,
+        } => Ok(FILE_ID_DESCRIPTOR {
+            dwSize: mem::size_of::<FILE_ID_DESCRIPTOR>() as u32,
+            Type: FileIdType,
+            Anonymous: FILE_ID_DESCRIPTOR_0 {
+                FileId: *file_index as i64,
+            },
+        }),
+
+        FileId::Inode { .. } => Err(Error::UnsupportedIdForPlatform { id: *file_id }),
+    }
+}
+
+/// Opens a file by its id on the volume at `volume_path_name`.
+unsafe fn open_file_by_id(
+    volume_path_name: &[u16],
+    file_id: &FileId,
+    options: &Options,
+) -> Result<fs::File, Error> {
+    let serial_number = SerialNumber::from_id(file_id)?;
+    let volume_handle = match get_volume_handle_from_path(volume_path_name) {
+        Ok(volume_handle) => volume_handle,
+        Err(err) => {
+            return Err(Error::VolumeHandle {
+                serial_number: Some(serial_number.value()),
+                source: err,
+            })
+        }
+    };
+
+    open_file_by_id_on(&volume_handle, file_id, options)
+}
+
+/// Opens a file by its id on the volume of `volume_handle`.
+///
+/// `OpenFileById` rejects the ids of some temporary file system resources with
+/// `ERROR_INVALID_PARAMETER`. In that case a high resolution id that fits in 64 bits
+/// is retried as a 64-bit id, and [`Error::TemporaryResourceUnsupported`] is returned
+/// if the file still can not be opened. Other ids fail with [`Error::OpenFile`].
+unsafe fn open_file_by_id_on(
+    volume_handle: &OwnedHandle,
+    file_id: &FileId,
+    options: &Options,
+) -> Result<fs::File, Error> {
+    use std::{os::raw::c_void, os::windows::prelude::*};
+    use windows_sys::Win32::{
+        Foundation::{ERROR_INVALID_PARAMETER, INVALID_HANDLE_VALUE},
+        Security::SECURITY_ATTRIBUTES,
+        Storage::FileSystem::{
+            FileIdType, OpenFileById, FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT,
+            FILE_ID_DESCRIPTOR_0,
+        },
+    };
+
+    let mut flags = FILE_FLAG_BACKUP_SEMANTICS;
+    if options.open_reparse_point || !options.traverse_mount_points {
+        flags |= FILE_FLAG_OPEN_REPARSE_POINT;
+    }
+
+    let serial_number = SerialNumber::from_id(file_id)?;
+    let file_id_descriptor = file_id_descriptor(file_id)?;
+    let open = |file_id_descriptor: &FILE_ID_DESCRIPTOR| {
+        OpenFileById(
+            volume_handle.as_raw_handle() as HANDLE,
+            file_id_descriptor as *const FILE_ID_DESCRIPTOR,
+            options.access,
+            options.share_mode,
+            null::<SECURITY_ATTRIBUTES>(),
+            flags,
+        )
+    };
+
+    let mut handle = open(&file_id_descriptor);
+    if handle == INVALID_HANDLE_VALUE {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(ERROR_INVALID_PARAMETER as i32) {
+            return Err(Error::OpenFile {
+                id: *file_id,
+                source: err,
+            });
+        }
+
+        let fallback_file_id = match file_id {
+            FileId::HighRes { file_id, .. } => i64::try_from(*file_id).ok(),
+            _ => None,
+        };
+
+        let Some(fallback_file_id) = fallback_file_id else {
+            return Err(Error::OpenFile {
+                id: *file_id,
+                source: err,
+            });
+        };
+
+        handle = open(&FILE_ID_DESCRIPTOR {
+            dwSize: mem::size_of::<FILE_ID_DESCRIPTOR>() as u32,
+            Type: FileIdType,
+            Anonymous: FILE_ID_DESCRIPTOR_0 {
+                FileId: fallback_file_id,
+            },
+        });
+
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(Error::TemporaryResourceUnsupported { id: *file_id });
+        }
+    }
+
+    let file = fs::File::from_raw_handle(handle as *mut c_void);
+
+    // Ids are only unique within a volume, so make sure the file is on the requested volume.
+    let volume_serial_number = get_volume_serial_number_from_handle(handle)?;
+    if !serial_number.matches(volume_serial_number) {
+        return Err(Error::VolumeMismatch {
+            expected: serial_number.value(),
+            found: volume_serial_number,
+        });
+    }
+
+    // Only mount points are kept as reparse points, so other reparse points are reopened followed.
+    if !options.open_reparse_point
+        && !options.traverse_mount_points
+        && is_reparse_point_other_than_mount_point(&file)
+    {
+        let options = Options {
+            traverse_mount_points: true,
+            ..*options
+        };
+
+        return open_file_by_id_on(volume_handle, file_id, &options);
+    }
+
+    Ok(file)
+}
+
+/// Whether the file is a reparse point, but not a mount point.
+unsafe fn is_reparse_point_other_than_mount_point(file: &fs::File) -> bool {
+    use windows_sys::Win32::Storage::FileSystem::{
+        FileAttributeTagInfo, GetFileInformationByHandleEx, FILE_ATTRIBUTE_REPARSE_POINT,
+        FILE_ATTRIBUTE_TAG_INFO,
+    };
+
+    /// `IO_REPARSE_TAG_MOUNT_POINT`, missing from the enabled `windows-sys` features.
+    const MOUNT_POINT: u32 = 0xA000_0003;
+
+    let mut info: FILE_ATTRIBUTE_TAG_INFO = mem::zeroed();
+    let ret = GetFileInformationByHandleEx(
+        file.as_raw_handle() as HANDLE,
+        FileAttributeTagInfo,
+        &mut info as *mut FILE_ATTRIBUTE_TAG_INFO as _,
+        mem::size_of::<FILE_ATTRIBUTE_TAG_INFO>() as u32,
+    );
+
+    ret != 0
+        && info.FileAttributes & FILE_ATTRIBUTE_REPARSE_POINT != 0
+        && info.ReparseTag != MOUNT_POINT
+}
+
+/// Volume serial number as carried by a [`FileId`].
+///
+/// Low resolution ids only carry the lower 32 bits of the volume serial number,
+/// so they are matched against the lower 32 bits of each volume's serial.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum SerialNumber {
+    Low(u32),
+    High(u64),
+}
+
+impl SerialNumber {
+    fn from_id(file_id: &FileId) -> Result<Self, Error> {
+        match file_id {
+            FileId::HighRes {
+                volume_serial_number,
+                file_id: _,
+            } => Ok(Self::High(*volume_serial_number)),
+
+            FileId::LowRes {
+                volume_serial_number,
+                file_index: _,
+            } => Ok(Self::Low(*volume_serial_number)),
+
+            id @ FileId::Inode { .. } => Err(Error::UnsupportedIdForPlatform { id: *id }),
+        }
+    }
+
+    fn matches(&self, serial_number: u64) -> bool {
+        match self {
+            Self::Low(sn) => *sn == serial_number as u32,
+            Self::High(sn) => *sn == serial_number,
+        }
+    }
+
+    fn value(&self) -> u64 {
+        match self {
+            Self::Low(sn) => *sn as u64,
+            Self::High(sn) => *sn,
+        }
+    }
+}
+
+/// Gets the serial number and path of the volume matching a serial number.
+///
+/// Drives are checked first using their 32-bit serial number,
+/// before enumerating every volume.
+unsafe fn get_volume_path_name_from_serial_number(
+    serial_number: SerialNumber,
+) -> Result<(u64, Vec<u16>), Error> {
+    if let Some(volume) = find_drive_by_serial_number(serial_number) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            volume_serial_number = volume.0,
+            "found volume of file id by drive serial number"
+        );
+
+        return Ok(volume);
+    }
+
+    find_volume(&SystemVolumes, serial_number)
+}
+
+/// Source of the mounted volumes, so volume selection can be run against a fixed set of volumes.
+trait VolumeEnumerator {
+    /// Calls `visit` with the path and serial number of each volume until it returns `true`.
+    fn visit_volumes(&self, visit: &mut dyn FnMut(Vec<u16>, u64) -> bool) -> Result<(), Error>;
+}
+
+/// The volumes mounted on the system.
+struct SystemVolumes;
+
+impl VolumeEnumerator for SystemVolumes {
+    fn visit_volumes(&self, visit: &mut dyn FnMut(Vec<u16>, u64) -> bool) -> Result<(), Error> {
+        unsafe { visit_volume_path_names(visit) }
+    }
+}
+
+/// Finds the volume matching a serial number.
+fn find_volume(
+    volumes: &impl VolumeEnumerator,
+    serial_number: SerialNumber,
+) -> Result<(u64, Vec<u16>), Error> {
+    let mut volume = None;
+    #[cfg(feature = "tracing")]
+    let mut scanned = 0_usize;
+    let visited = volumes.visit_volumes(&mut |path_name, volume_path_sn| {
+        #[cfg(feature = "tracing")]
+        {
+            scanned += 1;
+        }
+
+        if serial_number.matches(volume_path_sn) {
+            volume = Some((volume_path_sn, path_name));
+            true
+        } else {
+            false
+        }
+    });
+
+    match visited {
+        Ok(()) => {}
+        Err(Error::FindVolume {
+            serial_number: None,
+            source,
+        }) => {
+            return Err(Error::FindVolume {
+                serial_number: Some(serial_number.value()),
+                source,
+            })
+        }
+        Err(err) => return Err(err),
+    }
+
+    #[cfg(feature = "tracing")]
+    if let Some((volume_serial_number, _)) = &volume {
+        tracing::debug!(volume_serial_number, scanned, "found volume of file id");
+    }
+
+    volume.ok_or(Error::VolumeNotMounted {
+        serial_number: serial_number.value(),
+    })
+}
+
+/// Finds the drive matching a serial number using the 32-bit serial number
+/// from `GetVolumeInformationW`, which does not require opening the drive.
+///
+/// The 32-bit serial number is not guaranteed to be the lower half of the 64-bit one,
+/// and different volumes may share it, so candidates are confirmed
+/// by reading the 64-bit serial number from a handle.
+/// Returns `None` if no drive matched, in which case every volume should be enumerated.
+unsafe fn find_drive_by_serial_number(serial_number: SerialNumber) -> Option<(u64, Vec<u16>)> {
+    use windows_sys::Win32::Storage::FileSystem::GetVolumeInformationW;
+
+    let drives = get_logical_drives().ok()?;
+    for mut root in drives {
+        root.push(0);
+        let mut drive_sn: u32 = 0;
+        let ret = GetVolumeInformationW(
+            root.as_ptr(),
+            null_mut(),
+            0,
+            &mut drive_sn,
+            null_mut(),
+            null_mut(),
+            null_mut(),
+            0,
+        );
+
+        if ret == 0 || !SerialNumber::Low(serial_number.value() as u32).matches(drive_sn as u64) {
+            continue;
+        }
+
+        match get_volume_serial_number_from_path(&root) {
+            Ok(volume_sn) if serial_number.matches(volume_sn) => return Some((volume_sn, root)),
+            _ => continue,
+        }
+    }
+
+    None
+}
+
+/// Gets the root of each logical drive, e.g. `C:\`, without a terminating null.
+unsafe fn get_logical_drives() -> Result<Vec<Vec<u16>>, Error> {
+    use windows_sys::Win32::Storage::FileSystem::GetLogicalDriveStringsW;
+
+    let mut drives = vec![0; 4 * 26 + 1];
+    let mut size = GetLogicalDriveStringsW(drives.len() as u32, drives.as_mut_ptr());
+    if size as usize > drives.len() {
+        drives.resize(size as usize, 0);
+        size = GetLogicalDriveStringsW(drives.len() as u32, drives.as_mut_ptr());
+    }
+
+    if size == 0 {
+        return Err(Error::FindVolume {
+            serial_number: None,
+            source: io::Error::last_os_error(),
+        });
+    }
+
+    let drives = drives[..size as usize]
+        .split(|c| *c == 0)
+        .filter(|drive| !drive.is_empty())
+        .map(<[u16]>::to_vec)
+        .collect();
+
+    Ok(drives)
+}
+
+/// Gets the path of every mounted volume, keyed by serial number.
+unsafe fn get_volume_path_names_by_serial_number() -> Result<HashMap<u64, Vec<u16>>, Error> {
+    let mut volumes = HashMap::new();
+    visit_volume_path_names(|path_name, volume_path_sn| {
+        volumes.entry(volume_path_sn).or_insert(path_name);
+        false
+    })?;
+
+    Ok(volumes)
+}
+
+/// Calls `visit` with each volume path and its serial number
+/// until it returns `true`.
+///
+/// Local volumes are visited before mapped network drives.
+unsafe fn visit_volume_path_names(
+    mut visit: impl FnMut(Vec<u16>, u64) -> bool,
+) -> Result<(), Error> {
+    if visit_local_volume_path_names(&mut visit)? {
+        return Ok(());
+    }
+
+    visit_network_volume_path_names(&mut visit)?;
+    Ok(())
+}
+
+/// Calls `visit` with the path and serial number of each local volume
+/// until it returns `true`.
+///
+/// Returns whether `visit` stopped the enumeration.
+unsafe fn visit_local_volume_path_names(
+    visit: &mut impl FnMut(Vec<u16>, u64) -> bool,
+) -> Result<bool, Error> {
+    visit_volume_names(&mut |volume_name| visit_volume(volume_name, visit))
+}
+
+/// Calls `visit` with the null-terminated GUID name of each local volume
+/// until it returns `true` or an error.
+///
+/// Returns whether `visit` stopped the enumeration.
+unsafe fn visit_volume_names(
+    visit: &mut impl FnMut(&[u16]) -> Result<bool, Error>,
+) -> Result<bool, Error> {
+    use windows_sys::core::PWSTR;
+    use windows_sys::Win32::{
+        Foundation::{GetLastError, ERROR_NO_MORE_FILES, INVALID_HANDLE_VALUE, MAX_PATH},
+        Storage::FileSystem::{FindFirstVolumeW, FindNextVolumeW, FindVolumeClose},
+    };
+
+    let mut volume_name = [0; MAX_PATH as usize];
+    let volume_handle = FindFirstVolumeW(volume_name.as_mut_ptr() as PWSTR, MAX_PATH);
+    if volume_handle == INVALID_HANDLE_VALUE {
+        if GetLastError() == ERROR_NO_MORE_FILES {
+            return Ok(false);
+        }
+
+        return Err(Error::FindVolume {
+            serial_number: None,
+            source: io::Error::last_os_error(),
+        });
+    }
+
+    // `volume_name` holds the first volume, so it is visited before `FindNextVolumeW` is called.
+    let result = loop {
+        match visit(&volume_name) {
+            Ok(false) => {}
+            result => break result,
+        }
+
+        let ret = FindNextVolumeW(
+            volume_handle as HANDLE,
+            volume_name.as_mut_ptr() as PWSTR,
+            MAX_PATH,
+        );
+
+        if ret == 0 {
+            if GetLastError() == ERROR_NO_MORE_FILES {
+                break Ok(false);
+            }
+
+            break Err(Error::FindVolume {
+                serial_number: None,
+                source: io::Error::last_os_error(),
+            });
+        }
+    };
+
+    FindVolumeClose(volume_handle as HANDLE);
+    result
+}
 
-            let volume_handle = get_volume_handle_from_path(&volume_path_name)?;
-            let handle = OpenFileById(
-                volume_handle as HANDLE,
-                &file_id_descriptor as *const FILE_ID_DESCRIPTOR,
-                FILE_GENERIC_READ,
-                FILE_SHARE_READ,
-                null() as *const SECURITY_ATTRIBUTES,
-                FILE_FLAG_BACKUP_SEMANTICS,
-            );
+/// Calls `visit` with each path of the volume named `volume_name`.
+///
+/// Returns whether `visit` returned `true`.
+unsafe fn visit_volume(
+    volume_name: &[u16],
+    visit: &mut impl FnMut(Vec<u16>, u64) -> bool,
+) -> Result<bool, Error> {
+    let mut volume_path_names = get_volume_path_names(volume_name)?;
+    if volume_path_names.is_empty() {
+        // The volume has no drive letter or mount folder,
+        // so it can only be reached through its volume GUID path.
+        let len = volume_name
+            .iter()
+            .position(|c| *c == 0)
+            .unwrap_or(volume_name.len());
+        let mut path_name = volume_name[..len].to_vec();
+        path_name.push(0);
+        volume_path_names.push(path_name);
+    }
 
-            if handle == INVALID_HANDLE_VALUE {
-                return Err(Error::OpenFile(io::Error::last_os_error()));
-            }
+    for path_name in volume_path_names {
+        let volume_path_sn = match get_volume_serial_number_from_path(&path_name) {
+            Ok(volume_path_sn) => volume_path_sn,
+            Err(err) if is_volume_unavailable(&err) => continue,
+            Err(err) => return Err(err),
+        };
 
-            Ok(fs::File::from_raw_handle(handle as *mut c_void))
+        if visit(path_name, volume_path_sn) {
+            return Ok(true);
         }
+    }
 
-        FileId::LowRes {
-            volume_serial_number: _,
-            file_index: _,
-        } => todo!(),
+    Ok(false)
+}
 
-        FileId::Inode {
-            device_id: _,
-            inode_number: _,
-        } => return Err(Error::InvalidFileId),
-    }
+/// Whether the error is from a volume that can not currently be read,
+/// e.g. a card reader without a card, so it should be skipped when enumerating volumes.
+fn is_volume_unavailable(err: &Error) -> bool {
+    use windows_sys::Win32::Foundation::{
+        ERROR_DEVICE_NOT_CONNECTED, ERROR_NOT_READY, ERROR_NO_MEDIA_IN_DRIVE,
+        ERROR_UNRECOGNIZED_VOLUME,
+    };
+
+    const UNAVAILABLE: [u32; 4] = [
+        ERROR_NOT_READY,
+        ERROR_NO_MEDIA_IN_DRIVE,
+        ERROR_DEVICE_NOT_CONNECTED,
+        ERROR_UNRECOGNIZED_VOLUME,
+    ];
+
+    err.raw_os_error()
+        .is_some_and(|code| UNAVAILABLE.contains(&(code as u32)))
 }
 
-/// Gets the volume path from its serial number.
-unsafe fn get_volume_path_name_from_serial_number(serial_number: u64) -> Result<Vec<u16>, Error> {
-    use windows_sys::core::PWSTR;
+/// Calls `visit` with the UNC path and serial number of each mapped network drive
+/// until it returns `true`.
+///
+/// Drives mapped to the same share are only visited once,
+/// and drives that can not be reached are skipped.
+/// Returns whether `visit` stopped the enumeration.
+unsafe fn visit_network_volume_path_names(
+    visit: &mut impl FnMut(Vec<u16>, u64) -> bool,
+) -> Result<bool, Error> {
     use windows_sys::Win32::{
-        Foundation::{GetLastError, ERROR_NO_MORE_FILES, INVALID_HANDLE_VALUE, MAX_PATH},
-        Storage::FileSystem::{FindFirstVolumeW, FindNextVolumeW, FindVolumeClose},
+        Foundation::{MAX_PATH, NO_ERROR},
+        NetworkManagement::WNet::WNetGetConnectionW,
+        Storage::FileSystem::GetDriveTypeW,
+        System::WindowsProgramming::DRIVE_REMOTE,
     };
-    let volume_name = [0; MAX_PATH as usize];
-    let volume_handle = FindFirstVolumeW(volume_name.as_ptr() as PWSTR, MAX_PATH);
 
-    loop {
-        if volume_handle == INVALID_HANDLE_VALUE {
-            return Err(Error::FindVolume(io::Error::last_os_error()));
+    let mut visited = Vec::new();
+    for drive in get_logical_drives()? {
+        let mut root = drive.clone();
+        root.push(0);
+        if GetDriveTypeW(root.as_ptr()) != DRIVE_REMOTE {
+            continue;
         }
 
-        let volume_path_names = get_volume_path_names(&volume_name)?;
-        for path_name in volume_path_names {
-            let volume_path_sn = get_volume_serial_number_from_path(&path_name)?;
-            if volume_path_sn == serial_number {
-                return Ok(path_name);
-            }
+        // `WNetGetConnectionW` expects the drive without a trailing separator, e.g. `Z:`.
+        let local_name = [drive[0], drive[1], 0];
+        let mut remote_name = vec![0; MAX_PATH as usize];
+        let mut remote_name_size = remote_name.len() as u32;
+        let path_name = if WNetGetConnectionW(
+            local_name.as_ptr(),
+            remote_name.as_mut_ptr(),
+            &mut remote_name_size,
+        ) == NO_ERROR
+        {
+            let len = remote_name
+                .iter()
+                .position(|c| *c == 0)
+                .unwrap_or(remote_name.len());
+            remote_name.truncate(len);
+            remote_name.extend([b'\\' as u16, 0]);
+            remote_name
+        } else {
+            root
+        };
+
+        if visited.contains(&path_name) {
+            continue;
         }
 
-        let ret = FindNextVolumeW(
-            volume_handle as HANDLE,
-            volume_name.as_ptr() as PWSTR,
-            MAX_PATH,
-        );
+        let Ok(volume_path_sn) = get_volume_serial_number_from_path(&path_name) else {
+            continue;
+        };
 
-        if ret == 0 {
-            if GetLastError() == ERROR_NO_MORE_FILES {
-                FindVolumeClose(volume_handle as HANDLE);
-                break;
-            } else {
-                return Err(Error::FindVolume(io::Error::last_os_error()));
-            }
+        visited.push(path_name.clone());
+        if visit(path_name, volume_path_sn) {
+            return Ok(true);
         }
     }
 
-    Err(Error::FindVolume(io::Error::new(
-        io::ErrorKind::NotFound,
-        "no volume matching the serial number",
-    )))
+    Ok(false)
 }
 
 /// Get a paths within the given volume.
 unsafe fn get_volume_path_names(volume_name: &[u16]) -> Result<Vec<Vec<u16>>, Error> {
-    use windows_sys::core::PWSTR;
-    use windows_sys::Win32::{
-        Foundation::MAX_PATH, Storage::FileSystem::GetVolumePathNamesForVolumeNameW,
-    };
+    use windows_sys::Win32::Storage::FileSystem::GetVolumePathNamesForVolumeNameW;
 
-    let volume_paths = [0; MAX_PATH as usize];
-    let mut volume_paths_size: u32 = 0;
-    let ret = GetVolumePathNamesForVolumeNameW(
-        volume_name.as_ptr(),
-        volume_paths.as_ptr() as PWSTR,
-        MAX_PATH,
-        &mut volume_paths_size as *mut u32,
-    );
+    read_volume_path_names(|volume_paths, volume_paths_size| {
+        let ret = GetVolumePathNamesForVolumeNameW(
+            volume_name.as_ptr(),
+            volume_paths.as_mut_ptr(),
+            volume_paths.len() as u32,
+            volume_paths_size as *mut u32,
+        );
 
-    if ret == 0 {
-        return Err(Error::VolumePathNames(io::Error::last_os_error()));
+        if ret == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/// Reads a multi-string of volume paths with `get`, which fills the buffer
+/// and sets the required size, as `GetVolumePathNamesForVolumeNameW` does.
+///
+/// Volumes with many mount points may not fit in the initial buffer,
+/// in which case the required size is reported and the call is retried.
+fn read_volume_path_names(
+    mut get: impl FnMut(&mut [u16], &mut u32) -> io::Result<()>,
+) -> Result<Vec<Vec<u16>>, Error> {
+    use windows_sys::Win32::Foundation::{ERROR_MORE_DATA, MAX_PATH};
+
+    let mut volume_paths = vec![0; MAX_PATH as usize];
+    let mut volume_paths_size: u32 = 0;
+    loop {
+        match get(&mut volume_paths, &mut volume_paths_size) {
+            Ok(()) => break,
+            Err(err)
+                if err.raw_os_error() == Some(ERROR_MORE_DATA as i32)
+                    && volume_paths_size as usize > volume_paths.len() =>
+            {
+                volume_paths.resize(volume_paths_size as usize, 0);
+            }
+            Err(err) => return Err(Error::VolumePathNames(err)),
+        }
     }
 
+    let volume_paths = &volume_paths[..(volume_paths_size as usize).min(volume_paths.len())];
     let mut volume_path_names = Vec::with_capacity((volume_paths_size / 8) as usize);
     let mut volume_path = Vec::<u16>::with_capacity(8);
     let mut idx: usize = 0;
-    while idx < volume_paths_size as usize {
+    while idx < volume_paths.len() {
         let c = volume_paths[idx];
         if c == 0 {
-            if volume_path.len() > 0 {
+            if !volume_path.is_empty() {
                 volume_path.push(0); // terminating null byte
                 volume_path_names.push(volume_path.clone());
                 volume_path.clear();
@@ -195,15 +1821,33 @@ unsafe fn get_volume_path_names(volume_name: &[u16]) -> Result<Vec<Vec<u16>>, Er
     Ok(volume_path_names)
 }
 
-unsafe fn get_volume_serial_number_from_path(path_name: &Vec<u16>) -> Result<u64, Error> {
+/// Gets the 64-bit serial number of the volume at `path_name`.
+///
+/// The serial is read from `FILE_ID_INFO`, as for [`FileId::HighRes`] ids,
+/// rather than the 32-bit serial of `GetVolumeInformationW`, so ReFS volumes match their ids.
+unsafe fn get_volume_serial_number_from_path(path_name: &[u16]) -> Result<u64, Error> {
+    let file_handle = match get_volume_handle_from_path(path_name) {
+        Ok(file_handle) => file_handle,
+        Err(err) => {
+            return Err(Error::VolumeHandle {
+                serial_number: None,
+                source: err,
+            })
+        }
+    };
+
+    get_volume_serial_number_from_handle(file_handle.as_raw_handle() as HANDLE)
+}
+
+/// Gets the serial number of the volume an open file is on.
+unsafe fn get_volume_serial_number_from_handle(handle: HANDLE) -> Result<u64, Error> {
     use windows_sys::Win32::Storage::FileSystem::{
         FileIdInfo, GetFileInformationByHandleEx, FILE_ID_INFO,
     };
 
-    let file_handle = get_volume_handle_from_path(path_name)?;
     let mut info: FILE_ID_INFO = mem::zeroed();
     let ret = GetFileInformationByHandleEx(
-        file_handle,
+        handle,
         FileIdInfo,
         &mut info as *mut FILE_ID_INFO as _,
         mem::size_of::<FILE_ID_INFO>() as u32,
@@ -216,8 +1860,9 @@ unsafe fn get_volume_serial_number_from_path(path_name: &Vec<u16>) -> Result<u64
     Ok(info.VolumeSerialNumber)
 }
 
-unsafe fn get_volume_handle_from_path(path_name: &Vec<u16>) -> Result<HANDLE, Error> {
-    use std::os::raw::c_void;
+/// Opens a handle to the volume root, closed when dropped.
+unsafe fn get_volume_handle_from_path(path_name: &[u16]) -> io::Result<OwnedHandle> {
+    use std::os::{raw::c_void, windows::io::FromRawHandle};
     use windows_sys::Win32::{
         Foundation::INVALID_HANDLE_VALUE,
         Storage::FileSystem::{
@@ -236,19 +1881,847 @@ unsafe fn get_volume_handle_from_path(path_name: &Vec<u16>) -> Result<HANDLE, Er
     );
 
     if file_handle == INVALID_HANDLE_VALUE {
-        return Err(Error::VolumeHandle(io::Error::last_os_error()));
+        return Err(io::Error::last_os_error());
     }
 
-    Ok(file_handle as HANDLE)
+    Ok(OwnedHandle::from_raw_handle(file_handle as RawHandle))
 }
 
 #[derive(Debug)]
 pub enum Error {
-    InvalidFileId,
-    VolumeHandle(io::Error),
+    /// Could not open a volume.
+    /// `serial_number` is `None` if the volume was being opened to read its serial number.
+    VolumeHandle {
+        serial_number: Option<u64>,
+        source: io::Error,
+    },
+
     FileInformationByHandle(io::Error),
-    FindVolume(io::Error),
+
+    /// Could not enumerate volumes.
+    /// `serial_number` is the serial number being searched for, if any.
+    FindVolume {
+        serial_number: Option<u64>,
+        source: io::Error,
+    },
+
     VolumePathNames(io::Error),
-    OpenFile(io::Error),
+
+    /// Could not open the file with the id.
+    OpenFile {
+        id: FileId,
+        source: io::Error,
+    },
+
     FinalPathName(io::Error),
+    FileNames(io::Error),
+
+    /// No mounted volume has the serial number.
+    VolumeNotMounted {
+        serial_number: u64,
+    },
+
+    /// The id belongs to a temporary file system resource,
+    /// which can not be opened by id.
+    TemporaryResourceUnsupported {
+        id: FileId,
+    },
+
+    /// Could not get the root of the volume a path is on.
+    VolumeRoot(io::Error),
+
+    /// The open file has been deleted.
+    Deleted,
+
+    /// The id is a [`FileId::Inode`], e.g. of a file on a WSL or DrvFs mount,
+    /// which can not be opened by id on Windows.
+    UnsupportedIdForPlatform {
+        id: FileId,
+    },
+
+    /// The resolved path belongs to a different file than the id.
+    IdReused {
+        path: PathBuf,
+    },
+
+    /// The opened file is on a different volume than the id.
+    VolumeMismatch {
+        expected: u64,
+        found: u64,
+    },
+
+    /// The final path of the file is an NT device path,
+    /// e.g. `\Device\HarddiskVolume3\foo`, which is not mapped to a drive.
+    DevicePathUnresolved {
+        device_path: PathBuf,
+    },
+}
+
+impl Error {
+    /// Gets the underlying [`io::Error`], if the error wraps one.
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            Self::VolumeNotMounted { .. }
+            | Self::TemporaryResourceUnsupported { .. }
+            | Self::Deleted
+            | Self::UnsupportedIdForPlatform { .. }
+            | Self::IdReused { .. }
+            | Self::VolumeMismatch { .. }
+            | Self::DevicePathUnresolved { .. } => None,
+            Self::VolumeHandle { source, .. }
+            | Self::FindVolume { source, .. }
+            | Self::OpenFile { source, .. } => Some(source),
+            Self::FileInformationByHandle(err)
+            | Self::VolumePathNames(err)
+            | Self::FinalPathName(err)
+            | Self::FileNames(err)
+            | Self::VolumeRoot(err) => Some(err),
+        }
+    }
+
+    /// Gets the OS error code of the underlying [`io::Error`], if any.
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.io_error().and_then(io::Error::raw_os_error)
+    }
+
+    /// Whether the error may not occur if resolution is retried,
+    /// e.g. if a volume is being mounted or the file is locked.
+    pub fn is_transient(&self) -> bool {
+        use windows_sys::Win32::Foundation::{
+            ERROR_LOCK_VIOLATION, ERROR_NETNAME_DELETED, ERROR_NOT_READY, ERROR_SEM_TIMEOUT,
+            ERROR_SHARING_VIOLATION,
+        };
+
+        const TRANSIENT: [u32; 5] = [
+            ERROR_NOT_READY,
+            ERROR_SHARING_VIOLATION,
+            ERROR_LOCK_VIOLATION,
+            ERROR_NETNAME_DELETED,
+            ERROR_SEM_TIMEOUT,
+        ];
+
+        match self {
+            Self::VolumeNotMounted { .. } => true,
+            err => err
+                .raw_os_error()
+                .is_some_and(|code| TRANSIENT.contains(&(code as u32))),
+        }
+    }
+
+    /// Whether the id no longer maps to a live file,
+    /// e.g. because the file was deleted or its volume unmounted.
+    pub fn is_not_found(&self) -> bool {
+        use windows_sys::Win32::Foundation::{
+            ERROR_FILE_NOT_FOUND, ERROR_INVALID_PARAMETER, ERROR_PATH_NOT_FOUND,
+        };
+
+        const NOT_FOUND: [u32; 3] = [
+            ERROR_FILE_NOT_FOUND,
+            ERROR_PATH_NOT_FOUND,
+            ERROR_INVALID_PARAMETER,
+        ];
+
+        match self {
+            Self::VolumeNotMounted { .. }
+            | Self::TemporaryResourceUnsupported { .. }
+            | Self::Deleted
+            | Self::VolumeMismatch { .. } => true,
+            Self::OpenFile { source, .. } => source
+                .raw_os_error()
+                .is_some_and(|code| NOT_FOUND.contains(&(code as u32))),
+            _ => false,
+        }
+    }
+
+    /// Captures the error as an owned value that can be cloned.
+    pub fn snapshot(&self) -> crate::ErrorSnapshot {
+        let variant = match self {
+            Self::VolumeHandle { .. } => "VolumeHandle",
+            Self::FileInformationByHandle(_) => "FileInformationByHandle",
+            Self::FindVolume { .. } => "FindVolume",
+            Self::VolumePathNames(_) => "VolumePathNames",
+            Self::OpenFile { .. } => "OpenFile",
+            Self::FinalPathName(_) => "FinalPathName",
+            Self::FileNames(_) => "FileNames",
+            Self::VolumeNotMounted { .. } => "VolumeNotMounted",
+            Self::TemporaryResourceUnsupported { .. } => "TemporaryResourceUnsupported",
+            Self::VolumeRoot(_) => "VolumeRoot",
+            Self::Deleted => "Deleted",
+            Self::UnsupportedIdForPlatform { .. } => "UnsupportedIdForPlatform",
+            Self::IdReused { .. } => "IdReused",
+            Self::VolumeMismatch { .. } => "VolumeMismatch",
+            Self::DevicePathUnresolved { .. } => "DevicePathUnresolved",
+        };
+
+        crate::ErrorSnapshot::new(variant, self, self.io_error())
+    }
+}
+
+/// Errors wrapping an [`io::Error`] are equal if their [`io::ErrorKind`]s are.
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::VolumeHandle {
+                    serial_number: a_sn,
+                    source: a,
+                },
+                Self::VolumeHandle {
+                    serial_number: b_sn,
+                    source: b,
+                },
+            )
+            | (
+                Self::FindVolume {
+                    serial_number: a_sn,
+                    source: a,
+                },
+                Self::FindVolume {
+                    serial_number: b_sn,
+                    source: b,
+                },
+            ) => a_sn == b_sn && a.kind() == b.kind(),
+            (
+                Self::OpenFile {
+                    id: a_id,
+                    source: a,
+                },
+                Self::OpenFile {
+                    id: b_id,
+                    source: b,
+                },
+            ) => a_id == b_id && a.kind() == b.kind(),
+            (Self::FileInformationByHandle(a), Self::FileInformationByHandle(b))
+            | (Self::VolumePathNames(a), Self::VolumePathNames(b))
+            | (Self::FinalPathName(a), Self::FinalPathName(b))
+            | (Self::FileNames(a), Self::FileNames(b))
+            | (Self::VolumeRoot(a), Self::VolumeRoot(b)) => a.kind() == b.kind(),
+            (
+                Self::VolumeNotMounted { serial_number: a },
+                Self::VolumeNotMounted { serial_number: b },
+            ) => a == b,
+            (
+                Self::TemporaryResourceUnsupported { id: a },
+                Self::TemporaryResourceUnsupported { id: b },
+            ) => a == b,
+            (Self::Deleted, Self::Deleted) => true,
+            (
+                Self::VolumeMismatch {
+                    expected: a_expected,
+                    found: a_found,
+                },
+                Self::VolumeMismatch {
+                    expected: b_expected,
+                    found: b_found,
+                },
+            ) => a_expected == b_expected && a_found == b_found,
+            (
+                Self::UnsupportedIdForPlatform { id: a },
+                Self::UnsupportedIdForPlatform { id: b },
+            ) => a == b,
+            (Self::IdReused { path: a }, Self::IdReused { path: b }) => a == b,
+            (
+                Self::DevicePathUnresolved { device_path: a },
+                Self::DevicePathUnresolved { device_path: b },
+            ) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VolumeHandle {
+                serial_number: Some(serial_number),
+                source,
+            } => write!(
+                f,
+                "failed to open handle to volume {serial_number:x}: {source}"
+            ),
+            Self::VolumeHandle {
+                serial_number: None,
+                source,
+            } => write!(f, "failed to open volume handle: {source}"),
+            Self::FileInformationByHandle(err) => {
+                write!(f, "failed to get file information by handle: {err}")
+            }
+            Self::FindVolume {
+                serial_number: Some(serial_number),
+                source,
+            } => write!(f, "failed to find volume {serial_number:x}: {source}"),
+            Self::FindVolume {
+                serial_number: None,
+                source,
+            } => write!(f, "failed to find volumes: {source}"),
+            Self::VolumePathNames(err) => write!(f, "failed to get volume path names: {err}"),
+            Self::OpenFile { id, source } => {
+                write!(f, "failed to open file by id {id:?}: {source}")
+            }
+            Self::FinalPathName(err) => write!(f, "failed to get final path name: {err}"),
+            Self::FileNames(err) => write!(f, "failed to get hard link names: {err}"),
+            Self::VolumeRoot(err) => write!(f, "failed to get volume root: {err}"),
+            Self::VolumeNotMounted { serial_number } => {
+                write!(f, "no mounted volume with serial number {serial_number:x}")
+            }
+            Self::Deleted => write!(f, "file has been deleted"),
+            Self::VolumeMismatch { expected, found } => write!(
+                f,
+                "expected file on volume {expected:x}, but it is on volume {found:x}"
+            ),
+            Self::UnsupportedIdForPlatform { id } => {
+                write!(f, "{id:?} can not be resolved on Windows")
+            }
+            Self::IdReused { path } => {
+                write!(f, "{path:?} belongs to a different file than the id")
+            }
+            Self::DevicePathUnresolved { device_path } => write!(
+                f,
+                "device path {} is not mapped to a drive",
+                device_path.display()
+            ),
+            Self::TemporaryResourceUnsupported { id } => {
+                write!(
+                    f,
+                    "{id:?} is a temporary resource which can not be opened by id"
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::VolumeNotMounted { .. }
+            | Self::TemporaryResourceUnsupported { .. }
+            | Self::Deleted
+            | Self::UnsupportedIdForPlatform { .. }
+            | Self::IdReused { .. }
+            | Self::VolumeMismatch { .. }
+            | Self::DevicePathUnresolved { .. } => None,
+            Self::VolumeHandle { source, .. }
+            | Self::FindVolume { source, .. }
+            | Self::OpenFile { source, .. } => Some(source),
+            Self::FileInformationByHandle(err)
+            | Self::VolumePathNames(err)
+            | Self::FinalPathName(err)
+            | Self::FileNames(err)
+            | Self::VolumeRoot(err) => Some(err),
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match err {
+            Error::VolumeHandle { source, .. }
+            | Error::FindVolume { source, .. }
+            | Error::OpenFile { source, .. } => return source,
+            Error::FileInformationByHandle(err)
+            | Error::VolumePathNames(err)
+            | Error::FinalPathName(err)
+            | Error::FileNames(err)
+            | Error::VolumeRoot(err) => return err,
+            Error::VolumeNotMounted { .. } => io::ErrorKind::NotFound,
+            Error::TemporaryResourceUnsupported { .. } => io::ErrorKind::Unsupported,
+            Error::Deleted => io::ErrorKind::NotFound,
+            Error::UnsupportedIdForPlatform { .. } => io::ErrorKind::Unsupported,
+            Error::VolumeMismatch { .. } => io::ErrorKind::NotFound,
+            Error::DevicePathUnresolved { .. } => io::ErrorKind::NotFound,
+            Error::IdReused { .. } => io::ErrorKind::Other,
+        };
+
+        io::Error::new(kind, err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    /// Fixed set of volumes, as `(path, serial number)` pairs.
+    struct FakeVolumes(Vec<(&'static str, u64)>);
+
+    impl VolumeEnumerator for FakeVolumes {
+        fn visit_volumes(&self, visit: &mut dyn FnMut(Vec<u16>, u64) -> bool) -> Result<(), Error> {
+            for (path, serial_number) in &self.0 {
+                if visit(path.encode_utf16().collect(), *serial_number) {
+                    break;
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Enumerator that fails as `FindFirstVolumeW` would.
+    struct FailingVolumes;
+
+    impl VolumeEnumerator for FailingVolumes {
+        fn visit_volumes(&self, _: &mut dyn FnMut(Vec<u16>, u64) -> bool) -> Result<(), Error> {
+            Err(Error::FindVolume {
+                serial_number: None,
+                source: io::Error::from_raw_os_error(5),
+            })
+        }
+    }
+
+    #[test]
+    fn display() {
+        let err = Error::VolumeNotMounted {
+            serial_number: 0xabcd,
+        };
+
+        assert_eq!(err.to_string(), "no mounted volume with serial number abcd");
+    }
+
+    #[test]
+    fn source_of_io_errors() {
+        let err = Error::OpenFile {
+            id: FileId::new_high_res(1, 2),
+            source: io::Error::from_raw_os_error(2),
+        };
+        let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+
+        assert_eq!(source.raw_os_error(), Some(2));
+    }
+
+    #[test]
+    fn no_source_without_io_error() {
+        let err = Error::UnsupportedIdForPlatform {
+            id: FileId::new_inode(1, 2),
+        };
+
+        assert!(err.source().is_none());
+        assert!(Error::Deleted.source().is_none());
+    }
+
+    #[test]
+    fn uppercases_only_drive_letters() {
+        assert_eq!(
+            uppercase_drive_letter(PathBuf::from(r"\\?\c:\dir\file")),
+            PathBuf::from(r"\\?\C:\dir\file")
+        );
+        assert_eq!(
+            uppercase_drive_letter(PathBuf::from(r"c:\dir")),
+            PathBuf::from(r"C:\dir")
+        );
+        assert_eq!(
+            uppercase_drive_letter(PathBuf::from(r"\\server\share\c:")),
+            PathBuf::from(r"\\server\share\c:")
+        );
+    }
+
+    #[test]
+    fn strips_extended_prefix_from_drive_paths() {
+        assert_eq!(
+            strip_extended_prefix(PathBuf::from(r"\\?\C:\")),
+            PathBuf::from(r"C:\")
+        );
+        assert_eq!(
+            strip_extended_prefix(PathBuf::from(r"\\?\C:\dir\file")),
+            PathBuf::from(r"C:\dir\file")
+        );
+    }
+
+    #[test]
+    fn strips_extended_prefix_from_unc_paths() {
+        assert_eq!(
+            strip_extended_prefix(PathBuf::from(r"\\?\UNC\server\share")),
+            PathBuf::from(r"\\server\share")
+        );
+        assert_eq!(
+            strip_extended_prefix(PathBuf::from(r"\\?\UNC\server\share\dir")),
+            PathBuf::from(r"\\server\share\dir")
+        );
+    }
+
+    #[test]
+    fn keeps_extended_prefix_of_volume_guid_paths() {
+        let path = PathBuf::from(r"\\?\Volume{01234567-89ab-cdef-0123-456789abcdef}\dir");
+        assert_eq!(strip_extended_prefix(path.clone()), path);
+
+        let root = PathBuf::from(r"\\?\Volume{01234567-89ab-cdef-0123-456789abcdef}\");
+        assert_eq!(strip_extended_prefix(root.clone()), root);
+    }
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().collect()
+    }
+
+    /// Maps a device path through drives `C:` and `D:`, mapped to volumes 2 and 3.
+    fn map_fake_device_path(device_path: &str) -> Result<PathBuf, Error> {
+        let devices = [
+            ("C:", r"\Device\HarddiskVolume2"),
+            ("D:", r"\Device\HarddiskVolume3"),
+        ];
+        let drives = vec![wide(r"C:\"), wide(r"D:\"), wide(r"E:\")];
+
+        map_device_path(&wide(device_path), drives, |drive| {
+            devices
+                .iter()
+                .find(|(name, _)| wide(name) == drive)
+                .map(|(_, device)| wide(device))
+        })
+    }
+
+    #[test]
+    fn device_paths_map_to_their_drive() {
+        let cases = [
+            (r"\Device\HarddiskVolume2\dir\file", r"C:\dir\file"),
+            (r"\Device\HarddiskVolume3\file", r"D:\file"),
+            (r"\Device\HarddiskVolume3\", r"D:\"),
+            (r"\Device\HarddiskVolume3", r"D:"),
+        ];
+
+        for (device_path, expected) in cases {
+            assert_eq!(
+                map_fake_device_path(device_path).unwrap(),
+                PathBuf::from(expected),
+                "{device_path}"
+            );
+        }
+    }
+
+    #[test]
+    fn unmapped_device_paths_are_unresolved() {
+        let cases = [
+            r"\Device\HarddiskVolume4\file",
+            r"\Device\HarddiskVolume23\file",
+            r"\Device\Mup\server\share\file",
+        ];
+
+        for device_path in cases {
+            assert_eq!(
+                map_fake_device_path(device_path),
+                Err(Error::DevicePathUnresolved {
+                    device_path: PathBuf::from(device_path),
+                }),
+                "{device_path}"
+            );
+        }
+    }
+
+    #[test]
+    fn drive_letter_is_kept_by_default() {
+        assert!(!Options::default().uppercase_drive_letter);
+    }
+
+    #[test]
+    fn path_matches_canonicalize() {
+        let path = std::env::temp_dir().join(format!(
+            "file_path_from_id-canonicalize-{}.txt",
+            std::process::id()
+        ));
+        fs::write(&path, "canonicalize").unwrap();
+
+        let id = crate::id_from_path(&path).unwrap();
+        let options = Options {
+            uppercase_drive_letter: true,
+            ..Options::default()
+        };
+        let resolved = path_from_id_with(&id, &options);
+        let expected = fs::canonicalize(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(resolved.unwrap(), expected.unwrap());
+    }
+
+    #[test]
+    fn malformed_wide_path_is_kept_and_converted_lossily() {
+        use std::os::windows::ffi::OsStrExt;
+
+        let wide = [b'C' as u16, b':' as u16, b'\\' as u16, 0xd800, b'a' as u16];
+        let path = path_from_wide(&[&wide[..], &[0, b'x' as u16]].concat());
+
+        assert_eq!(path.as_os_str().encode_wide().collect::<Vec<_>>(), wide);
+        assert_eq!(path.to_string_lossy(), "C:\\\u{fffd}a");
+    }
+
+    /// Creates an empty directory under the temporary directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("file_path_from_id-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Sets the case sensitivity flag of an empty directory,
+    /// returning `false` if it is not supported, e.g. because WSL is not installed.
+    fn set_case_sensitive(dir: &Path) -> bool {
+        use std::os::windows::{fs::OpenOptionsExt, io::AsRawHandle};
+        use windows_sys::Win32::{
+            Storage::FileSystem::{
+                FileCaseSensitiveInfo, SetFileInformationByHandle, FILE_FLAG_BACKUP_SEMANTICS,
+                FILE_WRITE_ATTRIBUTES,
+            },
+            System::WindowsProgramming::FILE_CASE_SENSITIVE_INFO,
+        };
+
+        let dir = fs::OpenOptions::new()
+            .access_mode(FILE_WRITE_ATTRIBUTES)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(dir)
+            .unwrap();
+
+        let info = FILE_CASE_SENSITIVE_INFO { Flags: 0x1 };
+        let ret = unsafe {
+            SetFileInformationByHandle(
+                dir.as_raw_handle() as HANDLE,
+                FileCaseSensitiveInfo,
+                &info as *const FILE_CASE_SENSITIVE_INFO as _,
+                mem::size_of::<FILE_CASE_SENSITIVE_INFO>() as u32,
+            )
+        };
+
+        ret != 0
+    }
+
+    #[test]
+    fn case_insensitive_dir_uses_normalized_name() {
+        let dir = temp_dir("case-insensitive");
+        let path = dir.join("File.txt");
+        fs::write(&path, "normalized").unwrap();
+
+        let id = crate::id_from_path(dir.join("FILE.TXT")).unwrap();
+        let resolution = path_from_id_detailed(&id);
+        let cached = unsafe { is_case_sensitive_dir_cached(&fs::canonicalize(&dir).unwrap()) };
+        fs::remove_dir_all(&dir).unwrap();
+
+        let resolution = resolution.unwrap();
+        assert_eq!(resolution.path.file_name().unwrap(), "File.txt");
+        assert!(!resolution.warnings.contains(&ResolveWarning::OpenedName));
+        assert!(!cached);
+    }
+
+    /// Files in case-sensitive directories are named as opened,
+    /// so files differing only in case keep their own names.
+    #[test]
+    fn case_sensitive_dir_uses_opened_name() {
+        let dir = temp_dir("case-sensitive");
+        if !set_case_sensitive(&dir) {
+            fs::remove_dir_all(&dir).unwrap();
+            return;
+        }
+
+        fs::write(dir.join("File.txt"), "upper").unwrap();
+        fs::write(dir.join("file.txt"), "lower").unwrap();
+
+        let upper = path_from_id_detailed(&crate::id_from_path(dir.join("File.txt")).unwrap());
+        let lower = path_from_id_detailed(&crate::id_from_path(dir.join("file.txt")).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let (upper, lower) = (upper.unwrap(), lower.unwrap());
+        assert_eq!(upper.path.file_name().unwrap(), "File.txt");
+        assert_eq!(lower.path.file_name().unwrap(), "file.txt");
+        assert!(upper.warnings.contains(&ResolveWarning::OpenedName));
+    }
+
+    #[test]
+    fn all_paths_of_single_link_is_path() {
+        let dir = temp_dir("single-link");
+        let path = dir.join("file.txt");
+        fs::write(&path, "single").unwrap();
+
+        let id = crate::id_from_path(&path).unwrap();
+        let all = all_paths_from_id(&id);
+        let resolved = path_from_id(&id);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(all.unwrap(), vec![resolved.unwrap()]);
+    }
+
+    #[test]
+    fn all_paths_of_hard_linked_file() {
+        let dir = temp_dir("hard-links");
+        let path = dir.join("file.txt");
+        fs::write(&path, "linked").unwrap();
+        fs::hard_link(&path, dir.join("link.txt")).unwrap();
+
+        let id = crate::id_from_path(&path).unwrap();
+        let all = all_paths_from_id(&id);
+        let expected = [
+            fs::canonicalize(&path).unwrap(),
+            fs::canonicalize(dir.join("link.txt")).unwrap(),
+        ];
+        fs::remove_dir_all(&dir).unwrap();
+
+        let mut all = all.unwrap();
+        all.sort();
+        assert_eq!(all, expected);
+    }
+
+    /// Creates an empty file whose final path, including the `\\?\` prefix,
+    /// is `len` UTF-16 code units long, nesting directories under `root` as needed.
+    fn file_with_path_len(root: &Path, len: usize) -> PathBuf {
+        use std::os::windows::ffi::OsStrExt;
+
+        let mut dir = fs::canonicalize(root).unwrap();
+        loop {
+            let remaining = len - dir.as_os_str().encode_wide().count() - 1;
+            if remaining <= 200 {
+                let path = dir.join("f".repeat(remaining));
+                fs::write(&path, "").unwrap();
+                return path;
+            }
+
+            dir.push("d".repeat(100));
+            fs::create_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn path_lengths_around_max_path() {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Foundation::MAX_PATH;
+
+        let root = temp_dir("max-path");
+        let lens = [MAX_PATH - 2, MAX_PATH - 1, MAX_PATH, MAX_PATH + 1].map(|len| len as usize);
+        let results = lens
+            .iter()
+            .map(|len| {
+                let path = file_with_path_len(&root, *len);
+                let file = fs::File::open(&path).unwrap();
+                let resolved = unsafe {
+                    path_from_handle_buf(
+                        &file,
+                        NameMode::Normalized,
+                        PathStyle::Dos,
+                        &mut Vec::new(),
+                    )
+                };
+                (path, resolved)
+            })
+            .collect::<Vec<_>>();
+        fs::remove_dir_all(&root).unwrap();
+
+        for ((path, resolved), len) in results.into_iter().zip(lens) {
+            let resolved = resolved.unwrap();
+            assert_eq!(resolved.as_os_str().encode_wide().count(), len);
+            assert_eq!(resolved, path);
+        }
+    }
+
+    #[test]
+    fn volume_path_names_regrow_buffer() {
+        use windows_sys::Win32::Foundation::{ERROR_MORE_DATA, MAX_PATH};
+
+        let long = format!("C:\\mnt\\{}\\", "m".repeat(MAX_PATH as usize));
+        let names = ["D:\\", long.as_str(), "E:\\mnt\\"];
+        let multi_string = names
+            .iter()
+            .flat_map(|name| name.encode_utf16().chain([0]))
+            .chain([0])
+            .collect::<Vec<u16>>();
+
+        let mut calls = 0;
+        let volume_path_names = read_volume_path_names(|buf, size| {
+            calls += 1;
+            *size = multi_string.len() as u32;
+            if buf.len() < multi_string.len() {
+                return Err(io::Error::from_raw_os_error(ERROR_MORE_DATA as i32));
+            }
+
+            buf[..multi_string.len()].copy_from_slice(&multi_string);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(calls, 2);
+        let expected = names
+            .iter()
+            .map(|name| name.encode_utf16().chain([0]).collect::<Vec<u16>>())
+            .collect::<Vec<_>>();
+        assert_eq!(volume_path_names, expected);
+    }
+
+    #[test]
+    fn directory_has_no_trailing_separator() {
+        let dir = temp_dir("trailing-separator");
+        let resolved = path_from_id(&crate::id_from_path(&dir).unwrap());
+        let expected = fs::canonicalize(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let resolved = resolved.unwrap();
+        assert!(!resolved.as_os_str().to_string_lossy().ends_with('\\'));
+        assert_eq!(resolved, expected);
+    }
+
+    #[test]
+    fn volume_root_keeps_trailing_separator() {
+        let root = std::env::temp_dir()
+            .ancestors()
+            .last()
+            .unwrap()
+            .to_path_buf();
+        let resolved = path_from_id(&crate::id_from_path(&root).unwrap()).unwrap();
+
+        assert!(resolved.as_os_str().to_string_lossy().ends_with('\\'));
+        assert_eq!(resolved, fs::canonicalize(&root).unwrap());
+    }
+
+    #[test]
+    fn trim_trailing_separator_except_on_roots() {
+        assert_eq!(
+            trim_trailing_separator(PathBuf::from(r"\\?\C:\dir\")),
+            PathBuf::from(r"\\?\C:\dir")
+        );
+        assert_eq!(
+            trim_trailing_separator(PathBuf::from(r"\\?\C:\")),
+            PathBuf::from(r"\\?\C:\")
+        );
+    }
+
+    fn volumes() -> FakeVolumes {
+        FakeVolumes(vec![
+            ("C:\\", 0x1111_2222_3333_4444),
+            ("D:\\", 0x5555_6666_7777_8888),
+            ("E:\\", 0x9999_aaaa_7777_8888),
+        ])
+    }
+
+    #[test]
+    fn find_volume_high_res_matches_exactly() {
+        let (serial_number, path) =
+            find_volume(&volumes(), SerialNumber::High(0x9999_aaaa_7777_8888)).unwrap();
+
+        assert_eq!(serial_number, 0x9999_aaaa_7777_8888);
+        assert_eq!(String::from_utf16(&path).unwrap(), "E:\\");
+    }
+
+    #[test]
+    fn find_volume_low_res_matches_lower_32_bits() {
+        let (serial_number, path) =
+            find_volume(&volumes(), SerialNumber::Low(0x3333_4444)).unwrap();
+
+        assert_eq!(serial_number, 0x1111_2222_3333_4444);
+        assert_eq!(String::from_utf16(&path).unwrap(), "C:\\");
+    }
+
+    #[test]
+    fn find_volume_without_match_is_not_mounted() {
+        let err = find_volume(&volumes(), SerialNumber::High(0x7777_8888)).unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::VolumeNotMounted {
+                serial_number: 0x7777_8888
+            }
+        ));
+    }
+
+    #[test]
+    fn find_volume_error_includes_serial_number() {
+        let err = find_volume(&FailingVolumes, SerialNumber::Low(0x3333_4444)).unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::FindVolume {
+                serial_number: Some(0x3333_4444),
+                ..
+            }
+        ));
+    }
 }
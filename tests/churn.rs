@@ -0,0 +1,105 @@
+//! Creates, renames, and deletes files in rapid succession, resolving each file's id
+//! after every change as a watcher would.
+#![cfg(any(target_os = "linux", target_os = "macos", windows))]
+
+use file_path_from_id::{id_from_path, FileId};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+const ROUNDS: usize = 3;
+const FILES: usize = 10;
+
+#[cfg(target_os = "linux")]
+use file_path_from_id::unix::Error as PlatformError;
+#[cfg(target_os = "macos")]
+use file_path_from_id::unix::Error as PlatformError;
+#[cfg(windows)]
+use file_path_from_id::windows::Error as PlatformError;
+
+/// Resolves the id, searching only `root` so a deleted id does not walk the whole device.
+#[cfg(target_os = "linux")]
+fn resolve(id: &FileId, root: &Path) -> Result<PathBuf, PlatformError> {
+    use file_path_from_id::unix::{self, Options};
+    use std::time::Duration;
+
+    let options = Options {
+        search_roots: vec![root.to_path_buf()],
+        timeout: Some(Duration::from_secs(10)),
+    };
+
+    unix::path_from_id_with(id, &options)
+}
+
+/// Resolves the id, which does not require a search on this platform.
+#[cfg(target_os = "macos")]
+fn resolve(id: &FileId, _root: &Path) -> Result<PathBuf, PlatformError> {
+    file_path_from_id::unix::path_from_id(id)
+}
+
+/// Resolves the id, which does not require a search on this platform.
+#[cfg(windows)]
+fn resolve(id: &FileId, _root: &Path) -> Result<PathBuf, PlatformError> {
+    file_path_from_id::windows::path_from_id(id)
+}
+
+/// Checks that the id resolves to `path`, returning the failure.
+fn check_exists(id: &FileId, path: &Path, root: &Path) -> Option<String> {
+    let expected = fs::canonicalize(path).unwrap();
+    match resolve(id, root) {
+        Ok(resolved) if fs::canonicalize(&resolved).ok().as_ref() == Some(&expected) => None,
+        Ok(resolved) => Some(format!("mismatch: expected {expected:?}, got {resolved:?}")),
+        Err(err) => Some(format!("failed to resolve {expected:?}: {err}")),
+    }
+}
+
+/// Checks that the id of the deleted file at `path` no longer resolves,
+/// or resolves to a new file its id was given to, returning the failure.
+fn check_deleted(id: &FileId, path: &Path, root: &Path) -> Option<String> {
+    match resolve(id, root) {
+        Err(err) if err.is_not_found() => None,
+        Err(err) => Some(format!("deleted {path:?} failed with {err}")),
+        Ok(resolved) if id_from_path(&resolved).ok() != Some(*id) => None,
+        Ok(resolved) => Some(format!("deleted {path:?} resolved to {resolved:?}")),
+    }
+}
+
+#[test]
+fn churn() {
+    let root = env::temp_dir().join(format!("file_path_from_id-churn-{}", process::id()));
+    fs::create_dir_all(&root).unwrap();
+
+    let mut failures = Vec::new();
+    for round in 0..ROUNDS {
+        let mut files = (0..FILES)
+            .map(|index| {
+                let path = root.join(format!("{round}-{index}.txt"));
+                fs::write(&path, index.to_string()).unwrap();
+                (id_from_path(&path).unwrap(), path)
+            })
+            .collect::<Vec<_>>();
+
+        for (id, path) in &files {
+            failures.extend(check_exists(id, path, &root));
+        }
+
+        for (id, path) in &mut files {
+            let renamed = path.with_extension("renamed");
+            fs::rename(&*path, &renamed).unwrap();
+            *path = renamed;
+
+            failures.extend(check_exists(id, path, &root));
+        }
+
+        for (id, path) in files.iter().step_by(2) {
+            fs::remove_file(path).unwrap();
+
+            failures.extend(check_deleted(id, path, &root));
+        }
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+
+    assert!(failures.is_empty(), "{failures:#?}");
+}
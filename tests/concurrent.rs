@@ -0,0 +1,91 @@
+//! Resolves ids from several threads at once, contending on the global caches.
+#![cfg(any(target_os = "linux", target_os = "macos", windows))]
+
+use file_path_from_id::{id_from_path, FileId};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+
+const THREADS: usize = 8;
+const RESOLUTIONS: usize = 5;
+
+#[cfg(target_os = "linux")]
+use file_path_from_id::unix::Error as PlatformError;
+#[cfg(target_os = "macos")]
+use file_path_from_id::unix::Error as PlatformError;
+#[cfg(windows)]
+use file_path_from_id::windows::Error as PlatformError;
+
+/// Resolves the id, searching only `root` so a miss does not walk the whole device.
+#[cfg(target_os = "linux")]
+fn resolve(id: &FileId, root: &Path) -> Result<PathBuf, PlatformError> {
+    use file_path_from_id::unix::{self, Options};
+    use std::time::Duration;
+
+    let options = Options {
+        search_roots: vec![root.to_path_buf()],
+        timeout: Some(Duration::from_secs(10)),
+    };
+
+    unix::path_from_id_with(id, &options)
+}
+
+/// Resolves the id, which does not require a search on this platform.
+#[cfg(target_os = "macos")]
+fn resolve(id: &FileId, _root: &Path) -> Result<PathBuf, PlatformError> {
+    file_path_from_id::unix::path_from_id(id)
+}
+
+/// Resolves the id, which does not require a search on this platform.
+#[cfg(windows)]
+fn resolve(id: &FileId, _root: &Path) -> Result<PathBuf, PlatformError> {
+    file_path_from_id::windows::path_from_id(id)
+}
+
+#[test]
+fn resolve_from_threads() {
+    let root = env::temp_dir().join(format!("file_path_from_id-concurrent-{}", process::id()));
+    fs::create_dir_all(&root).unwrap();
+
+    let files = (0..THREADS)
+        .map(|index| {
+            let path = root.join(format!("{index}.txt"));
+            fs::write(&path, index.to_string()).unwrap();
+            (
+                id_from_path(&path).unwrap(),
+                fs::canonicalize(&path).unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let failures = thread::scope(|scope| {
+        let threads = files
+            .iter()
+            .map(|(id, expected)| {
+                let root = &root;
+                scope.spawn(move || {
+                    (0..RESOLUTIONS)
+                        .filter(|_| {
+                            resolve(id, root)
+                                .ok()
+                                .and_then(|path| fs::canonicalize(path).ok())
+                                .as_ref()
+                                != Some(expected)
+                        })
+                        .count()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .sum::<usize>()
+    });
+
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(failures, 0);
+}
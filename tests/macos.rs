@@ -0,0 +1,23 @@
+#![cfg(target_os = "macos")]
+
+use file_path_from_id::{id_from_path, path_from_id, Error};
+use std::env;
+use std::fs;
+use std::process;
+
+#[test]
+fn deleted_file_is_not_found() {
+    let path = env::temp_dir().join(format!("file_path_from_id-deleted-{}.txt", process::id()));
+    fs::write(&path, "deleted").unwrap();
+    let id = id_from_path(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(path_from_id(&id), Err(Error::NotFound));
+}
+
+#[test]
+fn volume_root_resolves_to_mountpoint() {
+    let id = id_from_path("/").unwrap();
+
+    assert_eq!(path_from_id(&id).unwrap(), std::path::PathBuf::from("/"));
+}
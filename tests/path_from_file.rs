@@ -0,0 +1,43 @@
+//! Gets the paths of open files, which should match `fs::canonicalize`.
+#![cfg(any(target_os = "linux", target_os = "macos", windows))]
+
+use file_path_from_id::path_from_file;
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::PathBuf;
+use std::process;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("file_path_from_id-{name}-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn path_of_open_file() -> io::Result<()> {
+    let dir = temp_dir("open-file");
+    let path = dir.join("file.txt");
+    fs::write(&path, "open")?;
+
+    let resolved = path_from_file(&File::open(&path)?);
+    let expected = fs::canonicalize(&path)?;
+    fs::remove_dir_all(&dir)?;
+
+    assert_eq!(resolved?, expected);
+    Ok(())
+}
+
+#[test]
+fn path_of_file_opened_through_relative_components() -> io::Result<()> {
+    let dir = temp_dir("relative-components");
+    fs::create_dir_all(dir.join("sub"))?;
+    fs::write(dir.join("file.txt"), "relative")?;
+
+    let resolved = path_from_file(&File::open(dir.join("sub").join("..").join("file.txt"))?);
+    let expected = fs::canonicalize(dir.join("file.txt"))?;
+    fs::remove_dir_all(&dir)?;
+
+    assert_eq!(resolved?, expected);
+    Ok(())
+}
@@ -0,0 +1,26 @@
+#![cfg(all(
+    feature = "tokio",
+    any(target_os = "linux", target_os = "macos", windows)
+))]
+
+use file_path_from_id::{id_from_path, path_from_id, path_from_id_async};
+use std::env;
+use std::fs;
+use std::process;
+
+#[test]
+fn matches_sync_resolution() {
+    let path = env::temp_dir().join(format!("file_path_from_id-async-{}.txt", process::id()));
+    fs::write(&path, "async").unwrap();
+    let id = id_from_path(&path).unwrap();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let resolved = runtime.block_on(path_from_id_async(&id));
+    let expected = path_from_id(&id);
+
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(resolved.unwrap(), expected.unwrap());
+}
@@ -0,0 +1,85 @@
+#![cfg(any(target_os = "linux", target_os = "macos", windows))]
+
+use file_path_from_id::{id_from_path, path_from_id_verified, Error, FileId};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Resolves and verifies the id, searching only `root`
+/// so a deleted id does not walk the whole device.
+#[cfg(target_os = "linux")]
+fn resolve_verified(id: &FileId, root: &Path) -> Result<PathBuf, Error> {
+    use file_path_from_id::unix::{self, Options};
+    use std::os::unix::fs::MetadataExt;
+    use std::time::Duration;
+
+    let options = Options {
+        search_roots: vec![root.to_path_buf()],
+        timeout: Some(Duration::from_secs(10)),
+    };
+
+    let path = unix::path_from_id_with(id, &options)?;
+    let metadata = fs::symlink_metadata(&path)?;
+    if FileId::new_inode(metadata.dev(), metadata.ino()) == *id {
+        Ok(path)
+    } else {
+        Err(Error::IdReused { path })
+    }
+}
+
+/// Resolves and verifies the id, which does not require a search on this platform.
+#[cfg(not(target_os = "linux"))]
+fn resolve_verified(id: &FileId, _root: &Path) -> Result<PathBuf, Error> {
+    path_from_id_verified(id)
+}
+
+#[test]
+fn live_file_verifies() {
+    let path = env::temp_dir().join(format!("file_path_from_id-verified-{}.txt", process::id()));
+    fs::write(&path, "live").unwrap();
+    let id = id_from_path(&path).unwrap();
+
+    let expected = fs::canonicalize(&path).unwrap();
+
+    let resolved = path_from_id_verified(&id).map(|path| fs::canonicalize(path).unwrap());
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(resolved.unwrap(), expected);
+}
+
+/// The id of a deleted file is likely given to one of the files created after it.
+/// Ids that only consist of a device and inode can not tell the new file apart,
+/// so a resolved path is accepted if the file at it has the same id.
+#[test]
+fn deleted_file_is_not_found_or_reused() {
+    let root = env::temp_dir().join(format!("file_path_from_id-reused-{}", process::id()));
+    fs::create_dir_all(&root).unwrap();
+
+    let deleted = root.join("deleted.txt");
+    fs::write(&deleted, "deleted").unwrap();
+    let id = id_from_path(&deleted).unwrap();
+    fs::remove_file(&deleted).unwrap();
+
+    let created = (0..10)
+        .map(|index| {
+            let path = root.join(format!("{index}.txt"));
+            fs::write(&path, index.to_string()).unwrap();
+            path
+        })
+        .collect::<Vec<_>>();
+
+    let resolved = resolve_verified(&id, &root);
+    let reused = created.iter().any(|path| id_from_path(path).unwrap() == id);
+
+    fs::remove_dir_all(&root).unwrap();
+
+    match resolved {
+        Err(Error::NotFound | Error::IdReused { .. }) => {}
+        Ok(path) => {
+            assert!(reused, "deleted id resolved to {path:?}");
+            assert_ne!(path.file_name(), deleted.file_name());
+        }
+        Err(err) => panic!("deleted id failed with {err}"),
+    }
+}
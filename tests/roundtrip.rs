@@ -0,0 +1,116 @@
+//! Creates a pseudo-random tree under a temporary root, then checks that every file
+//! resolves back to its path from its id.
+#![cfg(any(target_os = "linux", target_os = "macos", windows))]
+
+use file_path_from_id::{id_from_path, FileId};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+const FILES: usize = 100;
+const MAX_DEPTH: usize = 8;
+const NAME_PARTS: [&str; 8] = [
+    "a",
+    "file",
+    "ünïcödé",
+    "日本語",
+    "emoji-🦀",
+    "with space",
+    "dots.in.name",
+    "long-name-long-name-long-name-long-name-long-name",
+];
+
+#[cfg(target_os = "linux")]
+use file_path_from_id::unix::Error as PlatformError;
+#[cfg(target_os = "macos")]
+use file_path_from_id::unix::Error as PlatformError;
+#[cfg(windows)]
+use file_path_from_id::windows::Error as PlatformError;
+
+/// Resolves the id, searching only `root` so a miss does not walk the whole device.
+#[cfg(target_os = "linux")]
+fn resolve(id: &FileId, root: &Path) -> Result<PathBuf, PlatformError> {
+    use file_path_from_id::unix::{self, Options};
+    use std::time::Duration;
+
+    let options = Options {
+        search_roots: vec![root.to_path_buf()],
+        timeout: Some(Duration::from_secs(10)),
+    };
+
+    unix::path_from_id_with(id, &options)
+}
+
+/// Resolves the id, which does not require a search on this platform.
+#[cfg(target_os = "macos")]
+fn resolve(id: &FileId, _root: &Path) -> Result<PathBuf, PlatformError> {
+    file_path_from_id::unix::path_from_id(id)
+}
+
+/// Resolves the id, which does not require a search on this platform.
+#[cfg(windows)]
+fn resolve(id: &FileId, _root: &Path) -> Result<PathBuf, PlatformError> {
+    file_path_from_id::windows::path_from_id(id)
+}
+
+#[test]
+fn roundtrip() {
+    let mut rng = Rng(SEED);
+
+    let root = env::temp_dir().join(format!("file_path_from_id-roundtrip-{}", process::id()));
+    fs::create_dir_all(&root).unwrap();
+
+    let files = (0..FILES)
+        .map(|index| create_file(&root, index, &mut rng))
+        .collect::<Vec<_>>();
+
+    let mut failures = Vec::new();
+    for file in &files {
+        let id = id_from_path(file).unwrap();
+        let expected = fs::canonicalize(file).unwrap();
+        match resolve(&id, &root) {
+            Ok(path) if fs::canonicalize(&path).ok().as_ref() == Some(&expected) => {}
+            Ok(path) => failures.push(format!("mismatch: expected {expected:?}, got {path:?}")),
+            Err(err) => failures.push(format!("failed to resolve {expected:?}: {err}")),
+        }
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+
+    assert!(failures.is_empty(), "{failures:#?}");
+}
+
+/// Creates a file at a random depth below `root`.
+fn create_file(root: &Path, index: usize, rng: &mut Rng) -> PathBuf {
+    let mut dir = root.to_path_buf();
+    for _ in 0..rng.below(MAX_DEPTH + 1) {
+        dir.push(name(rng));
+    }
+
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}-{index}.txt", name(rng)));
+    fs::write(&path, index.to_string()).unwrap();
+    path
+}
+
+/// Builds a name from one to three random parts.
+fn name(rng: &mut Rng) -> String {
+    (0..=rng.below(3))
+        .map(|_| NAME_PARTS[rng.below(NAME_PARTS.len())])
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Xorshift generator, so every run creates the same tree.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+}
@@ -0,0 +1,56 @@
+//! Resolving ids must not leak handles, whether resolution succeeds or fails.
+//!
+//! Kept in its own test binary so other tests do not open handles while they are counted.
+#![cfg(windows)]
+
+use file_path_from_id::{id_from_path, path_from_id, FileId};
+use std::env;
+use std::fs;
+use std::process;
+use windows_sys::Win32::System::Threading::{GetCurrentProcess, GetProcessHandleCount};
+
+const RESOLUTIONS: usize = 100;
+
+fn handle_count() -> u32 {
+    let mut count = 0;
+    let ret = unsafe { GetProcessHandleCount(GetCurrentProcess(), &mut count) };
+    assert_ne!(ret, 0);
+    count
+}
+
+#[test]
+fn resolution_does_not_leak_handles() {
+    let dir = env::temp_dir().join(format!("file_path_from_id-handles-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let live = dir.join("live.txt");
+    fs::write(&live, "live").unwrap();
+    let deleted = dir.join("deleted.txt");
+    fs::write(&deleted, "deleted").unwrap();
+
+    let live_id = id_from_path(&live).unwrap();
+    let deleted_id = id_from_path(&deleted).unwrap();
+    fs::remove_file(&deleted).unwrap();
+
+    let unmounted_id = FileId::new_high_res(0x0123_4567_89ab_cdef, 1);
+    let resolve_all = || {
+        assert!(path_from_id(&live_id).is_ok());
+        assert!(path_from_id(&deleted_id).is_err());
+        assert!(path_from_id(&unmounted_id).is_err());
+    };
+
+    // Fill the process-wide caches before counting.
+    resolve_all();
+    let before = handle_count();
+    for _ in 0..RESOLUTIONS {
+        resolve_all();
+    }
+    let after = handle_count();
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(
+        after <= before,
+        "handle count grew from {before} to {after} over {RESOLUTIONS} resolutions"
+    );
+}